@@ -0,0 +1,53 @@
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+
+/// Decoded content of the headers "ice-audio-info" and "icy-audio-info"
+///
+/// # Example
+/// ```rust
+/// use av_stream_info_rust::AudioParameters;
+///
+/// let params = AudioParameters::from("ice-samplerate=44100;ice-bitrate=128;ice-channels=2");
+/// assert_eq!(params.bitrate, Some(128));
+/// assert_eq!(params.samplerate, Some(44100));
+/// assert_eq!(params.channels, Some(2));
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AudioParameters {
+    /// Bitrate in kbit/s
+    pub bitrate: Option<u32>,
+    /// Sampling rate in Hz
+    pub samplerate: Option<u32>,
+    pub channels: Option<u32>,
+    /// Encoder quality, mostly used with vorbis
+    pub quality: Option<f64>,
+}
+
+impl From<&str> for AudioParameters {
+    fn from(raw: &str) -> Self {
+        // some servers send the whole value url encoded
+        let decoded = percent_decode_str(raw).decode_utf8_lossy();
+        let mut params = AudioParameters::default();
+        for item in decoded.split([';', '&']) {
+            let mut key_value = item.splitn(2, '=');
+            let key = key_value.next().unwrap_or("").trim().to_lowercase();
+            let value = key_value.next().unwrap_or("").trim();
+            let key = key
+                .strip_prefix("ice-")
+                .or_else(|| key.strip_prefix("icy-"))
+                .unwrap_or(&key);
+            match key {
+                "bitrate" | "br" => params.bitrate = value.parse().ok(),
+                "samplerate" | "sr" => params.samplerate = value.parse().ok(),
+                "channels" => params.channels = value.parse().ok(),
+                "quality" => params.quality = value.parse().ok(),
+                _ => {
+                    if !key.is_empty() {
+                        trace!("unknown audio info key '{}'", key);
+                    }
+                }
+            }
+        }
+        params
+    }
+}
@@ -1,5 +1,6 @@
 use std::env;
-use av_stream_info_rust::check_tree;
+use std::time::Duration;
+use av_stream_info_rust::{check_tree_with_options, CheckOptions};
 extern crate log;
 extern crate env_logger;
 
@@ -18,6 +19,31 @@ fn main() {
         .unwrap_or(String::from("5"))
         .parse()
         .expect("RETRIES is not u8");
+    let playlist_concurrency: usize = env::var("PLAYLIST_CONCURRENCY")
+        .unwrap_or(String::from("1"))
+        .parse()
+        .expect("PLAYLIST_CONCURRENCY is not usize");
+    let html_links: bool = env::var("HTML_LINKS")
+        .unwrap_or(String::from("0"))
+        .parse::<u8>()
+        .expect("HTML_LINKS is not u8")
+        == 1;
+    let loudness_seconds: Option<u32> = env::var("LOUDNESS_SECONDS")
+        .ok()
+        .map(|s| s.parse().expect("LOUDNESS_SECONDS is not u32"));
+    let server_status: bool = env::var("SERVER_STATUS")
+        .unwrap_or(String::from("0"))
+        .parse::<u8>()
+        .expect("SERVER_STATUS is not u8")
+        == 1;
+    let meta_info_file: bool = env::var("META_INFO_FILE")
+        .unwrap_or(String::from("0"))
+        .parse::<u8>()
+        .expect("META_INFO_FILE is not u8")
+        == 1;
+    let deadline: Option<Duration> = env::var("DEADLINE")
+        .ok()
+        .map(|s| Duration::from_secs(s.parse().expect("DEADLINE is not u64")));
 
     /*
     println!("TCP_TIMEOUT   : {}", tcp_timeout);
@@ -27,7 +53,21 @@ fn main() {
 
     match env::args().nth(1) {
         Some(url) => {
-            let item = check_tree(&url, tcp_timeout, max_depth, retries, true);
+            let options = CheckOptions {
+                timeout: tcp_timeout,
+                max_depth,
+                retries,
+                early_exit_on_first_ok: true,
+                playlist_concurrency,
+                html_links,
+                loudness_seconds,
+                server_status,
+                meta_info_file,
+                meta_info_cache: None,
+                deadline,
+                cancellation: None,
+            };
+            let item = check_tree_with_options(&url, &options);
             println!("{:#?}", item);
         }
         None => {
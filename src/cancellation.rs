@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+/// Token to abort a running check from another thread.
+/// Cancelling closes all connections which are currently in use by the check.
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{CancellationToken, CheckOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = CheckOptions {
+///     cancellation: Some(token.clone()),
+///     ..CheckOptions::default()
+/// };
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_secs(5));
+///     token.cancel();
+/// });
+/// let item = av_stream_info_rust::check_tree_with_options("https://example.com/test.m3u", &options);
+/// println!("{:#?}", item);
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    next_id: AtomicU64,
+    streams: Mutex<HashMap<u64, TcpStream>>,
+    children: Mutex<Vec<Weak<Inner>>>,
+}
+
+impl Inner {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(streams) = self.streams.lock() {
+            for stream in streams.values() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+        if let Ok(children) = self.children.lock() {
+            for child in children.iter().filter_map(Weak::upgrade) {
+                child.cancel();
+            }
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Abort all checks which use this token or a child of it
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Create a token which is cancelled together with this one,
+    /// but can also be cancelled on its own.
+    pub fn child(&self) -> CancellationToken {
+        let child = CancellationToken::new();
+        if let Ok(mut children) = self.inner.children.lock() {
+            children.retain(|child| child.strong_count() > 0);
+            children.push(Arc::downgrade(&child.inner));
+        }
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+
+    /// Register a connection which gets closed on cancel, until the registration is dropped
+    pub(crate) fn register(&self, stream: &TcpStream) -> Option<Registration> {
+        let stream = stream.try_clone().ok()?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut streams) = self.inner.streams.lock() {
+            streams.insert(id, stream);
+        }
+        // cancel may have happened before insert
+        if self.is_cancelled() {
+            self.inner.cancel();
+        }
+        Some(Registration { token: self.clone(), id })
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CancellationToken(cancelled={})", self.is_cancelled())
+    }
+}
+
+/// Removes a registered connection from its token on drop
+pub(crate) struct Registration {
+    token: CancellationToken,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Ok(mut streams) = self.token.inner.streams.lock() {
+            streams.remove(&self.id);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+/// Guess encoding of bytes which are not valid UTF-8
+fn guess(bytes: &[u8]) -> &'static Encoding {
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Charset from the declaration of a XML document, e.g. <?xml version="1.0" encoding="ISO-8859-1"?>
+fn xml_declaration(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(200)]).to_lowercase();
+    let declaration = &head[head.find("<?xml")?..];
+    let declaration = &declaration[..declaration.find("?>")?];
+    let value = &declaration[declaration.find("encoding=")? + "encoding=".len()..];
+    let value = value.trim_start_matches(['"', '\'']);
+    let end = value.find(['"', '\'']).unwrap_or(value.len());
+    Encoding::for_label(value[..end].trim().as_bytes())
+}
+
+/// Decode text of a response body or a file. The encoding is taken from the byte order mark,
+/// the declared charset (e.g. from the content type) or a XML declaration, in this order.
+/// Without any of them, valid UTF-8 is used as is and the encoding of other content is guessed.
+/// UTF-8 is only trusted if the content is valid UTF-8, servers often declare it wrongly.
+pub fn decode(bytes: &[u8], declared: Option<&str>) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return encoding.decode_without_bom_handling(&bytes[bom_length..]).0.into_owned();
+    }
+    let declared = declared
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+        .or_else(|| xml_declaration(bytes));
+    match declared {
+        Some(encoding) if encoding != UTF_8 => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        _ => decode_guess(bytes),
+    }
+}
+
+/// Use valid UTF-8 as is, otherwise guess the encoding
+pub fn decode_guess(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => guess(bytes).decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+/// Decode the value of a response header. Values which are not valid UTF-8 are decoded with
+/// the encoding guessed from all headers of the response, single values are too short for a guess.
+pub fn decode_header(value: &[u8], all_headers: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) => text.to_string(),
+        Err(_) => guess(all_headers).decode_without_bom_handling(value).0.into_owned(),
+    }
+}
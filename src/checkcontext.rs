@@ -0,0 +1,110 @@
+use crate::CancellationToken;
+use crate::CheckEvent;
+use crate::CheckOptions;
+use crate::MetaInfoCache;
+use crate::StreamCheckError;
+
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// State shared by all checks of a tree
+pub struct CheckContext<'a> {
+    pub options: &'a CheckOptions,
+    observer: Option<&'a (dyn Fn(&CheckEvent) + Sync)>,
+    /// Child of the token in the options, also cancelled when the deadline is reached
+    pub cancellation: CancellationToken,
+    deadline: Option<Instant>,
+    /// Cache of the options, or stream information files of homepages which have been fetched during this check
+    pub meta_info_cache: Arc<MetaInfoCache>,
+    /// Stops the deadline watchdog thread when the context is dropped
+    _watchdog: Option<mpsc::Sender<()>>,
+}
+
+impl<'a> CheckContext<'a> {
+    pub fn new(options: &'a CheckOptions, observer: Option<&'a (dyn Fn(&CheckEvent) + Sync)>) -> Self {
+        let cancellation = match &options.cancellation {
+            Some(token) => token.child(),
+            None => CancellationToken::new(),
+        };
+        let deadline = options.deadline.map(|duration| Instant::now() + duration);
+        let _watchdog = options.deadline.map(|duration| {
+            let (sender, receiver) = mpsc::channel::<()>();
+            let token = cancellation.clone();
+            thread::spawn(move || {
+                if let Err(mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(duration) {
+                    debug!("deadline reached, cancel check");
+                    token.cancel();
+                }
+            });
+            sender
+        });
+        CheckContext {
+            options,
+            observer,
+            cancellation,
+            deadline,
+            meta_info_cache: options
+                .meta_info_cache
+                .clone()
+                .unwrap_or_else(|| Arc::new(MetaInfoCache::new(Duration::MAX))),
+            _watchdog,
+        }
+    }
+
+    /// Context for a part of the tree which can be cancelled on its own,
+    /// it is still cancelled together with this context.
+    pub fn child(&self) -> CheckContext<'a> {
+        CheckContext {
+            options: self.options,
+            observer: self.observer,
+            cancellation: self.cancellation.child(),
+            deadline: self.deadline,
+            meta_info_cache: self.meta_info_cache.clone(),
+            _watchdog: None,
+        }
+    }
+
+    /// Send event to the observer, the event is only created if there is one
+    pub fn emit<F: FnOnce() -> CheckEvent>(&self, event: F) {
+        if let Some(observer) = self.observer {
+            observer(&event());
+        }
+    }
+
+    /// Error for unfinished checks, if the check was cancelled or the deadline is reached
+    pub fn interrupted(&self) -> Option<StreamCheckError> {
+        if self.deadline.map(|deadline| Instant::now() >= deadline).unwrap_or(false) {
+            Some(StreamCheckError::DeadlineExceeded())
+        } else if self.cancellation.is_cancelled() {
+            Some(StreamCheckError::Cancelled())
+        } else {
+            None
+        }
+    }
+
+    /// TCP timeout in seconds, shortened to the time left until the deadline
+    pub fn timeout(&self) -> u32 {
+        match self.deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                let left = (left.as_millis() as u64).div_ceil(1000);
+                self.options.timeout.min(left.max(1) as u32)
+            }
+            None => self.options.timeout,
+        }
+    }
+
+    /// Sleep, but wake up early if the check gets interrupted
+    pub fn sleep(&self, duration: Duration) {
+        let end = Instant::now() + duration;
+        while self.interrupted().is_none() {
+            let left = end.saturating_duration_since(Instant::now());
+            if left == Duration::ZERO {
+                break;
+            }
+            thread::sleep(left.min(Duration::from_millis(100)));
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::StreamCheckError;
+use crate::StreamInfo;
+
+/// Progress events emitted while checking a tree of urls
+#[derive(Debug, Clone)]
+pub enum CheckEvent {
+    /// Checking of a url has started
+    Started { url: String },
+    /// Url did redirect to location, location will be checked next
+    Redirect { url: String, location: String, status: u32 },
+    /// Playlist has been decoded, entries will be checked next
+    PlaylistDecoded { url: String, entries: Vec<String> },
+    /// Url has been identified as a stream
+    StreamFound { url: String, info: Box<StreamInfo> },
+    /// Checking of url failed
+    Error { url: String, error: StreamCheckError },
+    /// No working stream has been found, the whole tree will be checked again
+    Retry { url: String, retries_left: u8 },
+}
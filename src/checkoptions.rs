@@ -0,0 +1,72 @@
+use crate::CancellationToken;
+use crate::MetaInfoCache;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Options for checking a tree of urls
+///
+/// # Example
+/// ```rust
+/// use av_stream_info_rust::CheckOptions;
+///
+/// let options = CheckOptions {
+///     timeout: 5,
+///     loudness_seconds: Some(10),
+///     ..CheckOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// TCP timeout for connect and read in seconds
+    pub timeout: u32,
+    /// How many layers of http redirects or playlists should be followed
+    pub max_depth: u8,
+    /// Retry how many times to find at least one working stream
+    pub retries: u8,
+    /// Return from checking as early as 1 working stream has been found
+    pub early_exit_on_first_ok: bool,
+    /// How many entries of a playlist are checked at the same time,
+    /// 1 checks them one after another
+    pub playlist_concurrency: usize,
+    /// Look for links to streams and playlists in html pages (audio tags, links,
+    /// web player configurations) and check them, instead of rejecting html pages
+    pub html_links: bool,
+    /// Decode this many seconds of every found stream to measure its loudness.
+    /// Needs the feature "loudness", without it the result is `LoudnessError::NotSupported`.
+    pub loudness_seconds: Option<u32>,
+    /// Fetch listener counts and other mount points from the status page of
+    /// Icecast and Shoutcast servers
+    pub server_status: bool,
+    /// Fetch "streaminfo.json" from the homepage of found streams and merge it
+    /// into the stream information, see [`MetaInfoFile::merge_into`](crate::MetaInfoFile::merge_into)
+    pub meta_info_file: bool,
+    /// Cache for stream information files which is shared between checks,
+    /// without it every check fetches them again
+    pub meta_info_cache: Option<Arc<MetaInfoCache>>,
+    /// Overall time limit for checking the whole tree including retries.
+    /// Unfinished checks end with StreamCheckError::DeadlineExceeded.
+    pub deadline: Option<Duration>,
+    /// Token to abort the check from another thread.
+    /// Unfinished checks end with StreamCheckError::Cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        CheckOptions {
+            timeout: 10,
+            max_depth: 3,
+            retries: 3,
+            early_exit_on_first_ok: true,
+            playlist_concurrency: 1,
+            html_links: false,
+            loudness_seconds: None,
+            server_status: false,
+            meta_info_file: false,
+            meta_info_cache: None,
+            deadline: None,
+            cancellation: None,
+        }
+    }
+}
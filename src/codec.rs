@@ -0,0 +1,337 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Profile of an AAC audio stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AacProfile {
+    /// AAC-LC
+    Lc,
+    /// HE-AAC, also known as AAC+
+    He,
+    /// HE-AAC v2, also known as AAC+ v2
+    HeV2,
+}
+
+/// Audio codec of a stream.
+///
+/// Serializes to the same strings that were used in former versions
+/// (e.g. "MP3", "AAC", "AAC+", "UNKNOWN").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum AudioCodec {
+    Mp3,
+    Aac(AacProfile),
+    Vorbis,
+    Opus,
+    Flac,
+    /// Ogg container with a codec that could not be determined
+    Ogg,
+    /// FLV container with a codec that could not be determined
+    Flv,
+    Unknown,
+}
+
+impl AudioCodec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioCodec::Mp3 => "MP3",
+            AudioCodec::Aac(AacProfile::Lc) => "AAC",
+            AudioCodec::Aac(AacProfile::He) => "AAC+",
+            AudioCodec::Aac(AacProfile::HeV2) => "AAC+v2",
+            AudioCodec::Vorbis => "VORBIS",
+            AudioCodec::Opus => "OPUS",
+            AudioCodec::Flac => "FLAC",
+            AudioCodec::Ogg => "OGG",
+            AudioCodec::Flv => "FLV",
+            AudioCodec::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl From<String> for AudioCodec {
+    fn from(codec: String) -> Self {
+        match codec.as_str() {
+            "MP3" => AudioCodec::Mp3,
+            "AAC" => AudioCodec::Aac(AacProfile::Lc),
+            "AAC+" => AudioCodec::Aac(AacProfile::He),
+            "AAC+v2" => AudioCodec::Aac(AacProfile::HeV2),
+            "VORBIS" => AudioCodec::Vorbis,
+            "OPUS" => AudioCodec::Opus,
+            "FLAC" => AudioCodec::Flac,
+            "OGG" => AudioCodec::Ogg,
+            "FLV" => AudioCodec::Flv,
+            _ => AudioCodec::Unknown,
+        }
+    }
+}
+
+impl From<AudioCodec> for String {
+    fn from(codec: AudioCodec) -> Self {
+        codec.as_str().to_string()
+    }
+}
+
+impl fmt::Display for AudioCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Profile of a H.264 video stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum H264Profile {
+    Baseline,
+    Main,
+    High,
+    Unknown,
+}
+
+/// Video codec of a stream.
+///
+/// Serializes to the same strings that were used in former versions (e.g. "H.264"),
+/// the H.264 profile is not part of them, see [`VideoCodec::profile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum VideoCodec {
+    H264(H264Profile),
+    H265,
+    Vp8,
+    Vp9,
+    Av1,
+    Theora,
+    Unknown,
+}
+
+impl VideoCodec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoCodec::H264(_) => "H.264",
+            VideoCodec::H265 => "H.265",
+            VideoCodec::Vp8 => "VP8",
+            VideoCodec::Vp9 => "VP9",
+            VideoCodec::Av1 => "AV1",
+            VideoCodec::Theora => "THEORA",
+            VideoCodec::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Profile of H.264 video, if it is known
+    pub fn profile(&self) -> Option<H264Profile> {
+        match self {
+            VideoCodec::H264(H264Profile::Unknown) => None,
+            VideoCodec::H264(profile) => Some(*profile),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for VideoCodec {
+    fn from(codec: String) -> Self {
+        match codec.as_str() {
+            "H.264" => VideoCodec::H264(H264Profile::Unknown),
+            "H.265" => VideoCodec::H265,
+            "VP8" => VideoCodec::Vp8,
+            "VP9" => VideoCodec::Vp9,
+            "AV1" => VideoCodec::Av1,
+            "THEORA" => VideoCodec::Theora,
+            _ => VideoCodec::Unknown,
+        }
+    }
+}
+
+impl From<VideoCodec> for String {
+    fn from(codec: VideoCodec) -> Self {
+        codec.as_str().to_string()
+    }
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Container format of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Container {
+    /// Plain MPEG audio frames
+    Mpeg,
+    /// AAC in ADTS frames
+    Adts,
+    Ogg,
+    Flac,
+    Flv,
+    Mp4,
+    MpegTs,
+    #[default]
+    Unknown,
+}
+
+impl Container {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Container::Mpeg => "MPEG",
+            Container::Adts => "ADTS",
+            Container::Ogg => "OGG",
+            Container::Flac => "FLAC",
+            Container::Flv => "FLV",
+            Container::Mp4 => "MP4",
+            Container::MpegTs => "MPEG-TS",
+            Container::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl From<String> for Container {
+    fn from(container: String) -> Self {
+        match container.as_str() {
+            "MPEG" => Container::Mpeg,
+            "ADTS" => Container::Adts,
+            "OGG" => Container::Ogg,
+            "FLAC" => Container::Flac,
+            "FLV" => Container::Flv,
+            "MP4" => Container::Mp4,
+            "MPEG-TS" => Container::MpegTs,
+            _ => Container::Unknown,
+        }
+    }
+}
+
+impl From<Container> for String {
+    fn from(container: Container) -> Self {
+        container.as_str().to_string()
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Map a content type to container and audio codec.
+/// "application/octet-stream" is only accepted if `allow_octet_stream` is set.
+pub fn from_mime(content_type: &str, allow_octet_stream: bool) -> Option<(Container, AudioCodec)> {
+    match content_type {
+        "audio/mpeg" | "audio/x-mpeg" | "audio/mp3" => Some((Container::Mpeg, AudioCodec::Mp3)),
+        "audio/aac" | "audio/x-aac" => Some((Container::Adts, AudioCodec::Aac(AacProfile::Lc))),
+        "audio/aacp" => Some((Container::Adts, AudioCodec::Aac(AacProfile::He))),
+        "audio/ogg" | "application/ogg" | "video/ogg" => Some((Container::Ogg, AudioCodec::Ogg)),
+        "audio/flac" => Some((Container::Flac, AudioCodec::Flac)),
+        "application/flv" => Some((Container::Flv, AudioCodec::Flv)),
+        "application/octet-stream" if allow_octet_stream => Some((Container::Unknown, AudioCodec::Unknown)),
+        _ => None,
+    }
+}
+
+/// Decode codecs attribute of HLS variant streams (RFC 6381)
+pub fn from_hls_codecs(codecs_raw: &str) -> (AudioCodec, Option<VideoCodec>) {
+    // codec information from
+    // https://developer.apple.com/library/content/documentation/NetworkingInternet/Conceptual/StreamingMediaGuide/FrequentlyAskedQuestions/FrequentlyAskedQuestions.html
+
+    let mut codec_audio = AudioCodec::Unknown;
+    let mut codec_video: Option<VideoCodec> = None;
+
+    for codec in codecs_raw.split(',') {
+        let codec = codec.trim().trim_matches('"').to_lowercase();
+        let mut parts = codec.splitn(2, '.');
+        let family = parts.next().unwrap_or("");
+        let details = parts.next().unwrap_or("");
+        match family {
+            "mp4a" => {
+                codec_audio = match details {
+                    // AAC-LC
+                    "40.2" => AudioCodec::Aac(AacProfile::Lc),
+                    // HE-AAC
+                    "40.5" => AudioCodec::Aac(AacProfile::He),
+                    // HE-AAC v2
+                    "40.29" => AudioCodec::Aac(AacProfile::HeV2),
+                    "40.34" | "69" | "6b" => AudioCodec::Mp3,
+                    _ => codec_audio,
+                };
+            }
+            "mp3" => codec_audio = AudioCodec::Mp3,
+            "opus" => codec_audio = AudioCodec::Opus,
+            "vorbis" => codec_audio = AudioCodec::Vorbis,
+            "flac" => codec_audio = AudioCodec::Flac,
+            "avc1" | "avc3" => codec_video = Some(VideoCodec::H264(h264_profile(details))),
+            "hvc1" | "hev1" => codec_video = Some(VideoCodec::H265),
+            "vp8" => codec_video = Some(VideoCodec::Vp8),
+            "vp09" | "vp9" => codec_video = Some(VideoCodec::Vp9),
+            "av01" => codec_video = Some(VideoCodec::Av1),
+            "theora" => codec_video = Some(VideoCodec::Theora),
+            _ => {}
+        }
+    }
+
+    (codec_audio, codec_video)
+}
+
+/// Decode profile from "avc1.PPCCLL" (hex) or the older "avc1.PP.LL" (decimal) notation
+fn h264_profile(details: &str) -> H264Profile {
+    let profile_idc = if details.contains('.') {
+        details.split('.').next().and_then(|p| p.parse::<u8>().ok())
+    } else {
+        details.get(0..2).and_then(|p| u8::from_str_radix(p, 16).ok())
+    };
+    match profile_idc {
+        Some(66) => H264Profile::Baseline,
+        Some(77) => H264Profile::Main,
+        Some(100) => H264Profile::High,
+        _ => H264Profile::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h264(profile: H264Profile) -> Option<VideoCodec> {
+        Some(VideoCodec::H264(profile))
+    }
+
+    #[test]
+    fn aac_profiles() {
+        assert_eq!(from_hls_codecs("mp4a.40.2"), (AudioCodec::Aac(AacProfile::Lc), None));
+        assert_eq!(from_hls_codecs("mp4a.40.5"), (AudioCodec::Aac(AacProfile::He), None));
+        assert_eq!(from_hls_codecs("mp4a.40.29"), (AudioCodec::Aac(AacProfile::HeV2), None));
+        assert_eq!(from_hls_codecs("MP4A.40.2"), (AudioCodec::Aac(AacProfile::Lc), None));
+        // xHE-AAC is not known
+        assert_eq!(from_hls_codecs("mp4a.40.42"), (AudioCodec::Unknown, None));
+    }
+
+    #[test]
+    fn mp3_object_types() {
+        assert_eq!(from_hls_codecs("mp4a.40.34").0, AudioCodec::Mp3);
+        assert_eq!(from_hls_codecs("mp4a.69").0, AudioCodec::Mp3);
+        assert_eq!(from_hls_codecs("mp4a.6B").0, AudioCodec::Mp3);
+        assert_eq!(from_hls_codecs("mp3").0, AudioCodec::Mp3);
+    }
+
+    #[test]
+    fn audio_with_video() {
+        assert_eq!(from_hls_codecs("avc1.42e01e,mp4a.40.2"), (AudioCodec::Aac(AacProfile::Lc), h264(H264Profile::Baseline)));
+        assert_eq!(from_hls_codecs("avc1.4d401f, mp4a.40.5"), (AudioCodec::Aac(AacProfile::He), h264(H264Profile::Main)));
+        assert_eq!(from_hls_codecs("\"avc1.640028,mp4a.40.29\""), (AudioCodec::Aac(AacProfile::HeV2), h264(H264Profile::High)));
+        // older decimal notation of the profile
+        assert_eq!(from_hls_codecs("avc1.66.30,mp4a.40.34"), (AudioCodec::Mp3, h264(H264Profile::Baseline)));
+        assert_eq!(from_hls_codecs("avc1"), (AudioCodec::Unknown, h264(H264Profile::Unknown)));
+        assert_eq!(from_hls_codecs("hvc1.1.6.L93.B0,mp4a.40.2").1, Some(VideoCodec::H265));
+        assert_eq!(from_hls_codecs("vp09.00.10.08,opus"), (AudioCodec::Opus, Some(VideoCodec::Vp9)));
+        assert_eq!(from_hls_codecs("av01.0.04M.08").1, Some(VideoCodec::Av1));
+        assert_eq!(from_hls_codecs("vorbis,theora"), (AudioCodec::Vorbis, Some(VideoCodec::Theora)));
+        assert_eq!(from_hls_codecs("ec-3,avc1.640028"), (AudioCodec::Unknown, h264(H264Profile::High)));
+        assert_eq!(from_hls_codecs(""), (AudioCodec::Unknown, None));
+    }
+
+    #[test]
+    fn former_strings() {
+        let main = VideoCodec::H264(H264Profile::Main);
+        assert_eq!(serde_json::to_string(&main).unwrap(), "\"H.264\"");
+        assert_eq!(main.profile(), Some(H264Profile::Main));
+        assert_eq!(serde_json::from_str::<VideoCodec>("\"H.264\"").unwrap(), VideoCodec::H264(H264Profile::Unknown));
+        assert_eq!(serde_json::to_string(&AudioCodec::Aac(AacProfile::He)).unwrap(), "\"AAC+\"");
+    }
+}
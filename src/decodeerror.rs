@@ -3,17 +3,140 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 /// Decoding errors for headers
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum DecodeError {
+///
+/// # Example
+/// ```rust
+/// use av_stream_info_rust::{DecodeErrorKind, LatLong};
+///
+/// let error = "91,200".parse::<LatLong>().unwrap_err();
+/// assert_eq!(error.kind, DecodeErrorKind::LatOutOfRange);
+/// assert_eq!(error.to_string(), "latitude out of range -90..90: \"91,200\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "DecodeErrorRepr")]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    /// Header or file field which contained the value, if known
+    pub field: Option<String>,
+    /// Value which could not be decoded
+    pub value: String,
+}
+
+/// What was wrong with a decoded value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecodeErrorKind {
     LatMissing,
     LongMissing,
     NumberParseError,
+    /// Latitude not in -90..90
+    LatOutOfRange,
+    /// Longitude not in -180..180
+    LongOutOfRange,
+    /// Not an ISO 3166-1 country code
+    InvalidCountryCode,
+    /// Not an ISO 3166-2 subdivision code
+    InvalidSubdivisionCode,
+    /// Not an ISO 639 language code
+    InvalidLanguageCode,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, value: &str) -> Self {
+        DecodeError {
+            kind,
+            field: None,
+            value: value.to_string(),
+        }
+    }
+
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeErrorKind::LatMissing => write!(f, "latitude missing"),
+            DecodeErrorKind::LongMissing => write!(f, "longitude missing"),
+            DecodeErrorKind::NumberParseError => write!(f, "not a valid coordinate"),
+            DecodeErrorKind::LatOutOfRange => write!(f, "latitude out of range -90..90"),
+            DecodeErrorKind::LongOutOfRange => write!(f, "longitude out of range -180..180"),
+            DecodeErrorKind::InvalidCountryCode => write!(f, "not an ISO 3166-1 country code"),
+            DecodeErrorKind::InvalidSubdivisionCode => write!(f, "not an ISO 3166-2 subdivision code"),
+            DecodeErrorKind::InvalidLanguageCode => write!(f, "not an ISO 639 language code"),
+        }
+    }
 }
 
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        match &self.field {
+            Some(field) => write!(f, "{}: {}: {:?}", field, self.kind, self.value),
+            None => write!(f, "{}: {:?}", self.kind, self.value),
+        }
     }
 }
 
 impl Error for DecodeError {}
+
+/// Also reads errors serialized by older versions, which were only the kind
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DecodeErrorRepr {
+    Full {
+        kind: DecodeErrorKind,
+        #[serde(default)]
+        field: Option<String>,
+        #[serde(default)]
+        value: String,
+    },
+    Legacy(DecodeErrorKind),
+}
+
+impl From<DecodeErrorRepr> for DecodeError {
+    fn from(repr: DecodeErrorRepr) -> Self {
+        match repr {
+            DecodeErrorRepr::Full { kind, field, value } => DecodeError { kind, field, value },
+            DecodeErrorRepr::Legacy(kind) => DecodeError::new(kind, ""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LatLong, StreamInfo, StreamRecord};
+
+    #[test]
+    fn legacy_errors() {
+        let error: DecodeError = serde_json::from_str("\"LatMissing\"").unwrap();
+        assert_eq!(error, DecodeError::new(DecodeErrorKind::LatMissing, ""));
+
+        let lat_long: Result<LatLong, DecodeError> = serde_json::from_str(r#"{"Err":"LatMissing"}"#).unwrap();
+        assert_eq!(lat_long.unwrap_err().kind, DecodeErrorKind::LatMissing);
+
+        let error = DecodeError::new(DecodeErrorKind::InvalidCountryCode, "XX").with_field("icy-country-code");
+        let serialized = serde_json::to_string(&error).unwrap();
+        assert_eq!(serde_json::from_str::<DecodeError>(&serialized).unwrap(), error);
+    }
+
+    #[test]
+    fn legacy_stream_info() {
+        let json = r#"{
+            "Server": null, "Public": null, "IceAudioInfo": null, "AudioInfo": null, "Name": "Radio",
+            "Description": null, "Type": "audio/mpeg", "Homepage": null, "Genre": null, "Bitrate": 128,
+            "Sampling": null, "CodecAudio": "MP3", "CodecVideo": null, "Hls": false, "LogoUrl": null,
+            "MainStreamUrl": null, "IcyVersion": 1, "OverrideIndexMetaData": null, "CountryCode": null,
+            "CountrySubdivisonCode": null, "LanguageCodes": [], "GeoLatLong": {"Err": "LatMissing"},
+            "DecodeErrors": ["InvalidCountryCode"], "DoNotIndex": null, "SslError": false
+        }"#;
+        let info: StreamInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.GeoLatLong.unwrap().unwrap_err().kind, DecodeErrorKind::LatMissing);
+        assert_eq!(info.DecodeErrors, vec![DecodeError::new(DecodeErrorKind::InvalidCountryCode, "")]);
+
+        let record: StreamRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.geo_lat_long.unwrap().unwrap_err().kind, DecodeErrorKind::LatMissing);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::Loudness;
+
+/// Second order IIR filter section
+#[derive(Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        // transposed direct form II
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[1] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[2] * y;
+        y
+    }
+}
+
+/// K-weighting filter from ITU-R BS.1770, with coefficients computed for any sample rate
+fn k_weighting(rate: u32) -> (Biquad, Biquad) {
+    let rate = rate as f64;
+
+    // high shelf
+    let f0 = 1681.974450955533;
+    let g = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10f64.powf(g / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    // high pass
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    (shelf, highpass)
+}
+
+/// Oversampling peak detector for true peak measurement
+struct TruePeak {
+    factor: usize,
+    taps: Vec<f64>,
+    history: Vec<f64>,
+    peak: f64,
+}
+
+impl TruePeak {
+    fn new(rate: u32) -> Self {
+        // oversample to at least 192kHz
+        let factor = if rate < 96000 { 4 } else if rate < 192000 { 2 } else { 1 };
+        let len = 12 * factor;
+        let center = (len - 1) as f64 / 2.0;
+        let taps = (0..len)
+            .map(|n| {
+                let x = (n as f64 - center) / factor as f64;
+                let sinc = if x == 0.0 { 1.0 } else { (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x) };
+                let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * (n as f64 + 0.5) / len as f64).cos();
+                sinc * window
+            })
+            .collect();
+        TruePeak {
+            factor,
+            taps,
+            history: vec![0.0; 12],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) {
+        self.history.rotate_right(1);
+        self.history[0] = x;
+        for phase in 0..self.factor {
+            let y: f64 = self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(k, h)| self.taps[phase + k * self.factor] * h)
+                .sum();
+            self.peak = self.peak.max(y.abs());
+        }
+        self.peak = self.peak.max(x.abs());
+    }
+}
+
+/// Loudness meter according to EBU R128
+pub struct Meter {
+    pub rate: u32,
+    pub channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<(Biquad, Biquad)>,
+    peaks: Vec<TruePeak>,
+    pub frames: u64,
+    /// mean square of the current 100ms sub block for each channel
+    current: Vec<f64>,
+    current_frames: usize,
+    /// weighted channel energy of all finished 100ms sub blocks
+    sub_blocks: Vec<f64>,
+}
+
+impl Meter {
+    pub fn new(rate: u32, channels: usize) -> Self {
+        // channel order L, R, C, LFE, Ls, Rs; LFE is not measured
+        let weights = (0..channels)
+            .map(|channel| match (channels, channel) {
+                (c, 3) if c >= 5 => 0.0,
+                (c, 4) | (c, 5) if c >= 5 => 1.41,
+                _ => 1.0,
+            })
+            .collect();
+        Meter {
+            rate,
+            channels,
+            weights,
+            filters: (0..channels).map(|_| k_weighting(rate)).collect(),
+            peaks: (0..channels).map(|_| TruePeak::new(rate)).collect(),
+            frames: 0,
+            current: vec![0.0; channels],
+            current_frames: 0,
+            sub_blocks: vec![],
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frames as f64 / self.rate as f64
+    }
+
+    /// Add interleaved samples
+    pub fn add(&mut self, samples: &[f32]) {
+        let sub_block_frames = (self.rate / 10) as usize;
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let x = *sample as f64;
+                self.peaks[channel].process(x);
+                let (shelf, highpass) = &mut self.filters[channel];
+                let y = highpass.process(shelf.process(x));
+                self.current[channel] += y * y;
+            }
+            self.frames += 1;
+            self.current_frames += 1;
+            if self.current_frames >= sub_block_frames {
+                let energy = self
+                    .current
+                    .iter()
+                    .zip(self.weights.iter())
+                    .map(|(sum, weight)| weight * sum / self.current_frames as f64)
+                    .sum();
+                self.sub_blocks.push(energy);
+                self.current.iter_mut().for_each(|sum| *sum = 0.0);
+                self.current_frames = 0;
+            }
+        }
+    }
+
+    /// Energies of overlapping blocks built from `len` sub blocks
+    fn blocks(&self, len: usize) -> Vec<f64> {
+        self.sub_blocks
+            .windows(len)
+            .map(|window| window.iter().sum::<f64>() / len as f64)
+            .collect()
+    }
+
+    pub fn result(&self) -> Loudness {
+        // integrated loudness from 400ms blocks with 75% overlap
+        let blocks: Vec<f64> = self.blocks(4).into_iter().filter(|e| loudness(*e) > -70.0).collect();
+        let integrated = mean(&blocks).and_then(|mean_energy| {
+            let relative_gate = loudness(mean_energy) - 10.0;
+            let gated: Vec<f64> = blocks.iter().cloned().filter(|e| loudness(*e) > relative_gate).collect();
+            mean(&gated).map(loudness)
+        });
+
+        // loudness range from 3s blocks
+        let blocks: Vec<f64> = self.blocks(30).into_iter().filter(|e| loudness(*e) > -70.0).collect();
+        let range = mean(&blocks).and_then(|mean_energy| {
+            let relative_gate = loudness(mean_energy) - 20.0;
+            let mut gated: Vec<f64> = blocks
+                .iter()
+                .map(|e| loudness(*e))
+                .filter(|l| *l > relative_gate)
+                .collect();
+            if gated.is_empty() {
+                return None;
+            }
+            gated.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            let percentile = |p: f64| gated[((gated.len() - 1) as f64 * p).round() as usize];
+            Some(percentile(0.95) - percentile(0.10))
+        });
+
+        let peak = self.peaks.iter().map(|p| p.peak).fold(0.0, f64::max);
+        let true_peak = if peak > 0.0 { Some(20.0 * peak.log10()) } else { None };
+
+        Loudness {
+            integrated,
+            range,
+            true_peak,
+            duration: self.duration(),
+        }
+    }
+}
+
+fn loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the value of a field in StreamInfo came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldSource {
+    /// Response headers of the stream
+    Headers,
+    /// "streaminfo.json" next to the homepage of the stream
+    MetaInfoFile,
+}
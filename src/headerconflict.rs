@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Different values for the same information found in multiple headers
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeaderConflict {
+    /// Name of the field in StreamInfo
+    pub field: String,
+    /// Header of the value which has been used
+    pub used_header: String,
+    pub used_value: String,
+    /// Header of the value which has been ignored
+    pub ignored_header: String,
+    pub ignored_value: String,
+}
+
+/// Use the first available value of the candidates which are given in order of
+/// precedence, all other values which differ from it are recorded as conflicts.
+pub fn reconcile<T: PartialEq + ToString>(
+    field: &str,
+    candidates: Vec<(&str, Option<T>)>,
+    conflicts: &mut Vec<HeaderConflict>,
+) -> Option<T> {
+    let mut used: Option<(&str, T)> = None;
+    for (header, value) in candidates {
+        if let Some(value) = value {
+            match &used {
+                Some((used_header, used_value)) => {
+                    if *used_value != value {
+                        warn!(
+                            "conflicting values for {}: {}={} and {}={}",
+                            field,
+                            used_header,
+                            used_value.to_string(),
+                            header,
+                            value.to_string()
+                        );
+                        conflicts.push(HeaderConflict {
+                            field: field.to_string(),
+                            used_header: used_header.to_string(),
+                            used_value: used_value.to_string(),
+                            ignored_header: header.to_string(),
+                            ignored_value: value.to_string(),
+                        });
+                    }
+                }
+                None => {
+                    used = Some((header, value));
+                }
+            }
+        }
+    }
+    used.map(|(_, value)| value)
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// Family of metadata headers a stream server did send
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HeaderDialect {
+    /// "icy-*" headers in a HTTP response (Icecast 2, Shoutcast 2 and most others)
+    Icy,
+    /// "icy-*" headers in a response with "ICY 200 OK" status line (Shoutcast 1)
+    ShoutcastV1,
+    /// "x-audiocast-*" headers (Icecast 1 and some hardware encoders)
+    XAudiocast,
+    /// No metadata headers
+    #[default]
+    None,
+}
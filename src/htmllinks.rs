@@ -0,0 +1,177 @@
+/// Tags whose "src" attribute points to media
+const MEDIA_TAGS: [&str; 4] = ["audio", "video", "source", "embed"];
+
+/// File extensions of playlists and streams
+const STREAM_EXTENSIONS: [&str; 11] = [
+    ".m3u", ".m3u8", ".pls", ".asx", ".xspf", ".mpd", ".mp3", ".aac", ".ogg", ".opus", ".flac",
+];
+
+/// Keys of common web player configurations, e.g. jPlayer `{mp3: "..."}` or `{"file": "..."}`
+const PLAYER_KEYS: [&str; 16] = [
+    "file", "stream", "streamurl", "stream_url", "streamsrc", "mp3", "aac", "m4a", "oga", "hls",
+    "hlsurl", "hls_url", "radiourl", "radio_url", "audiourl", "audio_url",
+];
+
+/// Find links to streams and playlists in a html page: "src" of audio, video, source and embed tags,
+/// any quoted url with a stream or playlist file extension and urls in common player configurations.
+/// Urls are returned as written in the page, in order of appearance and without duplicates.
+pub fn extract(html: &str) -> Vec<String> {
+    let mut list: Vec<String> = vec![];
+    let mut add = |link: String| {
+        if !link.is_empty() && !list.contains(&link) {
+            list.push(link);
+        }
+    };
+
+    for tag in tags(html) {
+        if MEDIA_TAGS.contains(&tag.name.as_str()) {
+            for (key, value) in tag.attributes {
+                if key == "src" || key == "data-src" {
+                    add(value);
+                }
+            }
+        }
+    }
+
+    for (key, value) in quoted_values(html) {
+        if has_stream_extension(&value) || (PLAYER_KEYS.contains(&key.as_str()) && is_absolute(&value)) {
+            add(value);
+        }
+    }
+
+    list.into_iter().filter(|link| !is_other_scheme(link)).collect()
+}
+
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+}
+
+/// Opening tags with their attributes, names are lower case
+fn tags(html: &str) -> Vec<Tag> {
+    let mut list = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let inner = &rest[..end];
+        let name_end = inner
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(inner.len());
+        let name = inner[..name_end].to_lowercase();
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            list.push(Tag {
+                name,
+                attributes: quoted_values(&inner[name_end..]),
+            });
+        }
+        rest = &rest[end..];
+    }
+    list
+}
+
+/// Quoted strings with the key in front of them, for `key="value"`, `key: 'value'` and `"key": "value"`.
+/// Keys are lower case, values are unescaped.
+fn quoted_values(text: &str) -> Vec<(String, String)> {
+    let mut list = vec![];
+    let mut index = 0;
+    while let Some(offset) = text[index..].find(['"', '\'']) {
+        let start = index + offset;
+        let quote = text[start..].chars().next().unwrap_or('"');
+        let value_start = start + 1;
+        let value_end = text[value_start..].find([quote, '\n']).map(|length| value_start + length);
+        let value = &text[value_start..value_end.unwrap_or(text.len())];
+        if let Some(value_end) = value_end.filter(|end| text[*end..].starts_with(quote) && !value.trim().contains(char::is_whitespace)) {
+            let key = key_before(&text[..start]);
+            list.push((key, unescape(value)));
+            index = value_end + 1;
+        } else {
+            // probably an apostrophe in text, the real quotes may start later
+            index = value_start;
+        }
+    }
+    list
+}
+
+/// Key in front of a value, skipping "=", ":" and quotes around the key
+fn key_before(text: &str) -> String {
+    let text = text.trim_end();
+    let text = match text.strip_suffix('=').or_else(|| text.strip_suffix(':')) {
+        Some(text) => text.trim_end(),
+        None => return String::new(),
+    };
+    let text = text.trim_end_matches(['"', '\'']);
+    let start = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-'))
+        .map(|(index, c)| index + c.len_utf8())
+        .unwrap_or(0);
+    text[start..].to_lowercase()
+}
+
+fn unescape(value: &str) -> String {
+    value.trim().replace("\\/", "/").replace("&amp;", "&")
+}
+
+fn has_stream_extension(link: &str) -> bool {
+    if link.contains(char::is_whitespace) || !(is_absolute(link) || link.contains('/') || link.contains('.')) {
+        return false;
+    }
+    let path = link.split(['?', '#']).next().unwrap_or("").to_lowercase();
+    STREAM_EXTENSIONS.iter().any(|extension| path.ends_with(extension))
+}
+
+fn is_absolute(link: &str) -> bool {
+    let lower = link.to_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("//")
+}
+
+/// Links which can not be checked, e.g. "javascript:" or "data:"
+fn is_other_scheme(link: &str) -> bool {
+    match link.find(':') {
+        Some(index) => {
+            let scheme = link[..index].to_lowercase();
+            scheme.chars().all(|c| c.is_ascii_alphabetic()) && scheme != "http" && scheme != "https"
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract;
+
+    #[test]
+    fn extract_links() {
+        let cases: Vec<(&str, Vec<&str>)> = vec![
+            (
+                r#"<audio controls><source src="/live.mp3" type="audio/mpeg"></audio><embed data-src='stream'>"#,
+                vec!["/live.mp3", "stream"],
+            ),
+            (r#"<p>Höre: "http://example.com/a.mp3"</p>"#, vec!["http://example.com/a.mp3"]),
+            (r#"<p>Größe=„x“ ünd: 'http://example.com/b.aac'</p>"#, vec!["http://example.com/b.aac"]),
+            (
+                r#"<p>It's our station's stream: <a href="/listen.pls">Listen</a></p>"#,
+                vec!["/listen.pls"],
+            ),
+            (
+                r##"<script>$("#player").jPlayer("setMedia", {title: "Radio", mp3: 'http://example.com/live', m4a: "http:\/\/example.com\/live.m4a?x=1&amp;y=2"});</script>"##,
+                vec!["http://example.com/live", "http://example.com/live.m4a?x=1&y=2"],
+            ),
+            (
+                r#"<script>var player = new Player({"streamUrl": "https://example.com/hls/live", "poster": "https://example.com/x.png"});</script>"#,
+                vec!["https://example.com/hls/live"],
+            ),
+            (
+                r#"<a href='javascript:play("x.mp3")'>js</a> <a href="javascript:x.mp3">x</a> <audio src="data:audio/mp3;base64,AAAA"></audio>"#,
+                vec![],
+            ),
+            (r#"<a href="/live.mp3">a</a> <a href="/live.mp3">b</a>"#, vec!["/live.mp3"]),
+            (r#"<a href="/about.html">About</a> <img src="/logo.png">"#, vec![]),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(extract(html), expected, "{}", html);
+        }
+    }
+}
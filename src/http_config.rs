@@ -1,11 +1,30 @@
+use crate::cancellation::CancellationToken;
+use crate::headerconflict;
+use crate::isocodes;
+use crate::request::Request;
+use crate::CheckOptions;
 use crate::DecodeError;
+use crate::FieldSource;
+use crate::HeaderConflict;
 use crate::LatLong;
+use crate::StreamInfo;
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use serde::de::{self, Deserializer, Unexpected};
 use std::error::Error;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use serde::de::{self, Deserializer, Unexpected};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
 
-use reqwest::blocking::get;
+const AGENT: &str = "StreamCheckBot/0.1.0";
+const FILE_NAME: &str = "streaminfo.json";
+const MAX_SIZE: usize = 100_000;
+const MAX_REDIRECTS: usize = 5;
+
+type CacheEntry = (Instant, Result<MetaInfoFile, MetaInfoError>);
 
 /// Does contain decoded information from a stream information file
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -33,38 +52,657 @@ pub struct MetaInfoFile {
     pub logo: Option<String>,
     #[serde(rename = "icy-geo-lat-long")]
     geo_lat_long: Option<String>,
+    /// Fields which were present but could not be used, they are left empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub field_errors: Vec<MetaInfoFieldError>,
 }
 
 impl MetaInfoFile {
     /// Decodes lat/long information contained in a stream information file
     pub fn get_lat_long(&self) -> Option<Result<LatLong, DecodeError>> {
-        self.geo_lat_long.clone().map(|x| LatLong::try_from(x))
+        self.geo_lat_long
+            .clone()
+            .map(|value| LatLong::try_from(value).map_err(|err| err.with_field("icy-geo-lat-long")))
+    }
+
+    /// Merge into the information from the stream headers. If "icy-index-metadata" is set in
+    /// the file or in the stream headers, the file values replace the header values, otherwise
+    /// they only fill fields the headers left empty. `OverrideIndexMetaData` tells afterwards
+    /// which of both was the case. The source of every merged field is recorded in
+    /// `FieldSources`, differing values of both sources in `HeaderConflicts`.
+    pub fn merge_into(&self, stream: &mut StreamInfo) {
+        let mut merge = Merge {
+            file_first: self.index_metadata || stream.OverrideIndexMetaData == Some(true),
+            sources: &mut stream.FieldSources,
+            conflicts: &mut stream.HeaderConflicts,
+        };
+        stream.Name = merge.field("Name", "icy-name", stream.Name.take(), self.name.clone());
+        stream.Description = merge.field("Description", "icy-description", stream.Description.take(), self.description.clone());
+        stream.Genre = merge.field("Genre", "icy-genre", stream.Genre.take(), self.genre.clone());
+        stream.LogoUrl = merge.field("LogoUrl", "icy-logo", stream.LogoUrl.take(), self.logo.clone());
+        stream.MainStreamUrl = merge.field("MainStreamUrl", "icy-main-stream-url", stream.MainStreamUrl.take(), self.main_stream_url.clone());
+        stream.CountryCode = merge.field("CountryCode", "icy-country-code", stream.CountryCode.take(), self.countrycode.clone());
+        stream.CountrySubdivisonCode = merge.field(
+            "CountrySubdivisonCode",
+            "icy-country-subdivision-code",
+            stream.CountrySubdivisonCode.take(),
+            self.country_subdivision_code.clone(),
+        );
+        let file_first = merge.file_first;
+        stream.OverrideIndexMetaData = merge
+            .field("OverrideIndexMetaData", "icy-index-metadata", stream.OverrideIndexMetaData, Some(self.index_metadata))
+            .map(|_| file_first);
+
+        let header_languages = Some(stream.LanguageCodes.join(",")).filter(|codes| !codes.is_empty());
+        let file_languages = self.languages.as_ref().map(|codes| split_codes(codes).join(",")).filter(|codes| !codes.is_empty());
+        stream.LanguageCodes = merge
+            .field("LanguageCodes", "icy-language-codes", header_languages, file_languages)
+            .map(|codes| split_codes(&codes))
+            .unwrap_or_default();
+
+        // only valid locations take part, an invalid header value is kept if the file has none
+        let header_valid = matches!(stream.GeoLatLong, Some(Ok(_)));
+        let file_lat_long = self.get_lat_long().filter(|lat_long| lat_long.is_ok());
+        let use_file = file_lat_long.is_some() && (merge.file_first || !header_valid);
+        if use_file {
+            stream.GeoLatLong = file_lat_long;
+            merge.sources.insert(String::from("GeoLatLong"), FieldSource::MetaInfoFile);
+        } else if header_valid {
+            merge.sources.insert(String::from("GeoLatLong"), FieldSource::Headers);
+        }
+    }
+}
+
+fn split_codes(codes: &str) -> Vec<String> {
+    codes
+        .split(',')
+        .map(|code| code.trim())
+        .filter(|code| !code.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+struct Merge<'a> {
+    file_first: bool,
+    sources: &'a mut HashMap<String, FieldSource>,
+    conflicts: &'a mut Vec<HeaderConflict>,
+}
+
+impl<'a> Merge<'a> {
+    fn field<T: PartialEq + ToString>(&mut self, field: &str, header: &str, from_headers: Option<T>, from_file: Option<T>) -> Option<T> {
+        let headers = (header, from_headers, FieldSource::Headers);
+        let file = (FILE_NAME, from_file, FieldSource::MetaInfoFile);
+        let (first, second) = if self.file_first { (file, headers) } else { (headers, file) };
+        let source = if first.1.is_some() {
+            Some(first.2)
+        } else if second.1.is_some() {
+            Some(second.2)
+        } else {
+            None
+        };
+        if let Some(source) = source {
+            self.sources.insert(field.to_string(), source);
+        }
+        headerconflict::reconcile(field, vec![(first.0, first.1), (second.0, second.1)], self.conflicts)
     }
 }
 
+/// Field of a stream information file with an unusable value
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetaInfoFieldError {
+    /// Name of the field in the file, e.g. "icy-logo"
+    pub field: String,
+    /// Value as written in the file
+    pub value: String,
+    /// What the value should have been
+    pub expected: String,
+}
+
+impl fmt::Display for MetaInfoFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: expected {}, found {}", self.field, self.expected, self.value)
+    }
+}
+
+/// Posible errors for fetching a stream information file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MetaInfoError {
+    UrlParseError(),
+    ConnectionFailed(),
+    IllegalStatusCode(u32),
+    TooManyRedirects(),
+    DecodeError(),
+}
+
+impl fmt::Display for MetaInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetaInfoError::UrlParseError() => write!(f, "UrlParseError()"),
+            MetaInfoError::ConnectionFailed() => write!(f, "ConnectionFailed()"),
+            MetaInfoError::IllegalStatusCode(code) => write!(f, "IllegalStatusCode({})", code),
+            MetaInfoError::TooManyRedirects() => write!(f, "TooManyRedirects()"),
+            MetaInfoError::DecodeError() => write!(f, "DecodeError()"),
+        }
+    }
+}
+
+impl Error for MetaInfoError {}
+
+/// Download and decode the stream information file of a homepage with default options
 pub fn extract_from_homepage(homepage: &str) -> Result<MetaInfoFile, Box<dyn Error>> {
-    let stream_info_link = format!("{}/streaminfo.json", homepage);
+    Ok(fetch_meta_info_file(homepage, &CheckOptions::default())?)
+}
+
+/// Download and decode "streaminfo.json" next to a homepage, e.g. "http://example.com/radio/"
+/// and "http://example.com/radio" both use "http://example.com/radio/streaminfo.json".
+/// Uses timeout and cancellation of the options. Malformed fields do not fail the whole file,
+/// they are left empty and listed in `field_errors`.
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{fetch_meta_info_file, CheckOptions};
+///
+/// let file = fetch_meta_info_file("https://example.com/", &CheckOptions::default()).unwrap();
+/// for error in file.field_errors.iter() {
+///     println!("{}", error);
+/// }
+/// ```
+pub fn fetch_meta_info_file(homepage: &str, options: &CheckOptions) -> Result<MetaInfoFile, MetaInfoError> {
+    fetch(homepage, options.timeout, options.cancellation.as_ref())
+}
+
+/// Fetch the stream information file, abort if the cancellation token is cancelled
+pub(crate) fn fetch(homepage: &str, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<MetaInfoFile, MetaInfoError> {
+    let url = meta_info_url(homepage)?;
+    trace!("fetch_meta_info_file({}) Download file '{}'", homepage, url);
+    let (url, content) = download(url, timeout, cancellation)?;
+    let json: Value = serde_json::from_str(&content).or(Err(MetaInfoError::DecodeError()))?;
+    if !json.is_object() {
+        return Err(MetaInfoError::DecodeError());
+    }
+    Ok(decode(&json, &url))
+}
 
-    trace!(
-        "extract_from_homepage({}) Download file '{}'",
-        homepage,
-        stream_info_link
+/// Url of the stream information file, the homepage is treated as a directory
+fn meta_info_url(homepage: &str) -> Result<Url, MetaInfoError> {
+    let mut url = Url::parse(homepage.trim()).or(Err(MetaInfoError::UrlParseError()))?;
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    url.join(FILE_NAME).or(Err(MetaInfoError::UrlParseError()))
+}
+
+/// Download the file following redirects, returns the final url and the content
+fn download(mut url: Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<(Url, String), MetaInfoError> {
+    for _ in 0..=MAX_REDIRECTS {
+        let mut request = Request::new(url.as_str(), AGENT, timeout, cancellation)
+            .or(Err(MetaInfoError::ConnectionFailed()))?;
+        match request.info.code {
+            200 => {
+                request.read_up_to(MAX_SIZE).or(Err(MetaInfoError::ConnectionFailed()))?;
+                return Ok((url, request.text(None)));
+            }
+            301 | 302 | 303 | 307 | 308 => {
+                let location = request.info.headers.get("location").ok_or(MetaInfoError::IllegalStatusCode(request.info.code))?;
+                url = url.join(location).or(Err(MetaInfoError::UrlParseError()))?;
+                debug!("stream information file redirected to '{}'", url);
+            }
+            code => return Err(MetaInfoError::IllegalStatusCode(code)),
+        }
+    }
+    Err(MetaInfoError::TooManyRedirects())
+}
+
+fn decode(json: &Value, file_url: &Url) -> MetaInfoFile {
+    let mut errors = vec![];
+    let index_metadata = index_metadata_field(json, &mut errors);
+    let version = version_field(json, &mut errors);
+    let main_stream_url = url_field(json, "icy-main-stream-url", file_url, &mut errors);
+    let name = text_field(json, "icy-name", &mut errors);
+    let description = text_field(json, "icy-description", &mut errors);
+    let genre = text_field(json, "icy-genre", &mut errors);
+    let languages = language_codes_field(json, &mut errors);
+    let countrycode = code_field(json, "icy-country-code", isocodes::country_code, "ISO 3166-1 country code", &mut errors);
+    let country_subdivision_code = code_field(
+        json,
+        "icy-country-subdivision-code",
+        isocodes::subdivision_code,
+        "ISO 3166-2 subdivision code",
+        &mut errors,
     );
-    let resp = get(&stream_info_link)?.text()?;
-    let deserialized: MetaInfoFile = serde_json::from_str(&resp)?;
-    Ok(deserialized)
+    let logo = url_field(json, "icy-logo", file_url, &mut errors);
+    let geo_lat_long = text_field(json, "icy-geo-lat-long", &mut errors);
+
+    let geo_lat_long = geo_lat_long.filter(|value| match LatLong::try_from(value.clone()) {
+        Ok(_) => true,
+        Err(err) => {
+            errors.push(field_error("icy-geo-lat-long", &Value::String(value.clone()), &format!("latitude,longitude ({})", err.kind)));
+            false
+        }
+    });
+
+    MetaInfoFile {
+        index_metadata,
+        version,
+        main_stream_url,
+        name,
+        description,
+        genre,
+        languages,
+        countrycode,
+        country_subdivision_code,
+        logo,
+        geo_lat_long,
+        field_errors: errors,
+    }
+}
+
+fn field_error(field: &str, value: &Value, expected: &str) -> MetaInfoFieldError {
+    MetaInfoFieldError {
+        field: field.to_string(),
+        value: match value {
+            Value::String(value) => value.clone(),
+            other => other.to_string(),
+        },
+        expected: expected.to_string(),
+    }
 }
 
+/// Non empty string, missing and null fields are None without error
+fn text_field(json: &Value, field: &str, errors: &mut Vec<MetaInfoFieldError>) -> Option<String> {
+    match &json[field] {
+        Value::Null => None,
+        Value::String(value) => Some(value.trim().to_string()).filter(|value| !value.is_empty()),
+        Value::Number(value) => Some(value.to_string()),
+        other => {
+            errors.push(field_error(field, other, "string"));
+            None
+        }
+    }
+}
+
+/// Absolute http(s) url, relative urls are resolved against the url of the file
+fn url_field(json: &Value, field: &str, file_url: &Url, errors: &mut Vec<MetaInfoFieldError>) -> Option<String> {
+    let value = text_field(json, field, errors)?;
+    match file_url.join(&value) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Some(url.to_string()),
+        _ => {
+            errors.push(field_error(field, &Value::String(value), "http or https url"));
+            None
+        }
+    }
+}
+
+/// Normalised country or subdivision code
+fn code_field<F>(json: &Value, field: &str, normalise: F, expected: &str, errors: &mut Vec<MetaInfoFieldError>) -> Option<String>
+where
+    F: Fn(&str) -> Result<String, DecodeError>,
+{
+    let value = text_field(json, field, errors)?;
+    match normalise(&value) {
+        Ok(code) => Some(code),
+        Err(_) => {
+            errors.push(field_error(field, &Value::String(value), expected));
+            None
+        }
+    }
+}
+
+/// Comma separated normalised language codes, invalid codes are left out
+fn language_codes_field(json: &Value, errors: &mut Vec<MetaInfoFieldError>) -> Option<String> {
+    let field = "icy-language-codes";
+    let value = text_field(json, field, errors)?;
+    let mut codes: Vec<String> = vec![];
+    for code in split_codes(&value) {
+        match isocodes::language_code(&code) {
+            Ok(code) => {
+                if !codes.contains(&code) {
+                    codes.push(code);
+                }
+            }
+            Err(_) => errors.push(field_error(field, &Value::String(code), "ISO 639 language code")),
+        }
+    }
+    Some(codes.join(",")).filter(|codes| !codes.is_empty())
+}
+
+/// 0 or 1, also accepts booleans and numbers as strings, false if missing
+fn index_metadata_field(json: &Value, errors: &mut Vec<MetaInfoFieldError>) -> bool {
+    let field = "icy-index-metadata";
+    let value = &json[field];
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        _ => match value.as_u64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok())) {
+            Some(0) => false,
+            Some(1) => true,
+            _ => {
+                errors.push(field_error(field, value, "0 or 1"));
+                false
+            }
+        },
+    }
+}
+
+/// Positive number, also accepts numbers as strings, 1 if missing
+fn version_field(json: &Value, errors: &mut Vec<MetaInfoFieldError>) -> u8 {
+    let field = "icy-version";
+    let value = &json[field];
+    if value.is_null() {
+        return 1;
+    }
+    match value.as_u64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok())) {
+        Some(version) if version >= 1 && version <= u8::MAX as u64 => version as u8,
+        _ => {
+            errors.push(field_error(field, value, "version number"));
+            1
+        }
+    }
+}
+
+/// Cache of stream information files per homepage. Missing and undecodable files are cached
+/// too, most homepages do not have a stream information file. Failed connections are not cached. Checks use it if it is set in
+/// [`CheckOptions::meta_info_cache`](crate::CheckOptions::meta_info_cache).
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{CheckOptions, MetaInfoCache};
+/// use std::time::Duration;
+///
+/// let cache = MetaInfoCache::new(Duration::from_secs(3600));
+/// let file = cache.get("https://example.com/", &CheckOptions::default());
+/// println!("{:#?}", file);
+/// ```
+pub struct MetaInfoCache {
+    expiry: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl fmt::Debug for MetaInfoCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MetaInfoCache(expiry={:?}, entries={})", self.expiry, self.lock().len())
+    }
+}
+
+impl MetaInfoCache {
+    /// Entries are fetched again once they are older than expiry
+    pub fn new(expiry: Duration) -> Self {
+        MetaInfoCache {
+            expiry,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached result for the homepage, fetched with [`fetch_meta_info_file`] if missing or expired
+    pub fn get(&self, homepage: &str, options: &CheckOptions) -> Result<MetaInfoFile, MetaInfoError> {
+        self.get_or_fetch(homepage, || fetch_meta_info_file(homepage, options))
+    }
+
+    pub(crate) fn get_or_fetch<F>(&self, homepage: &str, fetch: F) -> Result<MetaInfoFile, MetaInfoError>
+    where
+        F: FnOnce() -> Result<MetaInfoFile, MetaInfoError>,
+    {
+        if let Some((fetched, result)) = self.lock().get(homepage) {
+            if fetched.elapsed() < self.expiry {
+                return result.clone();
+            }
+        }
+        // not locked while downloading, concurrent misses of the same homepage fetch twice
+        let result = fetch();
+        // connection failures, also those from deadline or cancellation, may be gone at the next check
+        let definitive = matches!(result, Ok(_) | Err(MetaInfoError::IllegalStatusCode(_)) | Err(MetaInfoError::DecodeError()));
+        if definitive {
+            self.lock().insert(homepage.to_string(), (Instant::now(), result.clone()));
+        }
+        result
+    }
+
+    /// Remove expired entries
+    pub fn remove_expired(&self) {
+        let expiry = self.expiry;
+        self.lock().retain(|_, (fetched, _)| fetched.elapsed() < expiry);
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 0 or 1 as written in stream information files, also accepts booleans as written
+/// by serializing a `MetaInfoFile` and numbers as strings
 fn bool_from_int<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
 {
-    match u8::deserialize(deserializer)? {
-        0 => Ok(false),
-        1 => Ok(true),
-        other => Err(de::Error::invalid_value(
-            Unexpected::Unsigned(other as u64),
+    let value = Value::deserialize(deserializer)?;
+    if let Value::Bool(value) = value {
+        return Ok(value);
+    }
+    match value.as_u64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok())) {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(de::Error::invalid_value(
+            Unexpected::Other(&value.to_string()),
             &"zero or one",
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_metadata_round_trip() {
+        let cases = [("true", true), ("1", true), ("\"1\"", true), ("false", false), ("0", false), ("\" 0 \"", false)];
+        for (value, expected) in cases.iter() {
+            let json = format!("{{\"icy-index-metadata\": {}, \"icy-version\": 1}}", value);
+            let file: MetaInfoFile = serde_json::from_str(&json).unwrap();
+            assert_eq!(file.index_metadata, *expected, "{}", value);
+
+            let serialized = serde_json::to_string(&file).unwrap();
+            let file: MetaInfoFile = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(file.index_metadata, *expected, "{}", serialized);
+        }
+        for value in ["2", "\"yes\"", "null"].iter() {
+            let json = format!("{{\"icy-index-metadata\": {}, \"icy-version\": 1}}", value);
+            assert!(serde_json::from_str::<MetaInfoFile>(&json).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn meta_info_url_of_directory() {
+        for homepage in ["http://x/radio", "http://x/radio/", " http://x/radio/ "].iter() {
+            assert_eq!(meta_info_url(homepage).unwrap().as_str(), "http://x/radio/streaminfo.json", "{}", homepage);
+        }
+        assert_eq!(meta_info_url("http://x").unwrap().as_str(), "http://x/streaminfo.json");
+        assert!(meta_info_url("radio").is_err());
+    }
+
+    #[test]
+    fn decode_keeps_good_fields() {
+        let json = serde_json::json!({
+            "icy-index-metadata": "1",
+            "icy-version": "x",
+            "icy-main-stream-url": "/live.mp3",
+            "icy-name": " Test Radio ",
+            "icy-description": "",
+            "icy-genre": ["rock"],
+            "icy-language-codes": "en, xx, de",
+            "icy-country-code": "de",
+            "icy-country-subdivision-code": "Berlin",
+            "icy-logo": "logo.png",
+            "icy-geo-lat-long": "91,0",
+        });
+        let file_url = Url::parse("http://x/radio/streaminfo.json").unwrap();
+        let file = decode(&json, &file_url);
+
+        assert!(file.index_metadata);
+        assert_eq!(file.version, 1);
+        assert_eq!(file.main_stream_url.as_deref(), Some("http://x/live.mp3"));
+        assert_eq!(file.name.as_deref(), Some("Test Radio"));
+        assert_eq!(file.description, None);
+        assert_eq!(file.genre, None);
+        assert_eq!(file.languages.as_deref(), Some("en,de"));
+        assert_eq!(file.countrycode.as_deref(), Some("DE"));
+        assert_eq!(file.country_subdivision_code, None);
+        assert_eq!(file.logo.as_deref(), Some("http://x/radio/logo.png"));
+        assert!(file.get_lat_long().is_none());
+
+        let errors: Vec<(&str, &str)> = file.field_errors.iter().map(|error| (error.field.as_str(), error.value.as_str())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("icy-version", "x"),
+                ("icy-genre", "[\"rock\"]"),
+                ("icy-language-codes", "xx"),
+                ("icy-country-subdivision-code", "Berlin"),
+                ("icy-geo-lat-long", "91,0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_field_types() {
+        let file_url = Url::parse("https://x/streaminfo.json").unwrap();
+        let file = decode(&serde_json::json!({}), &file_url);
+        assert!(!file.index_metadata);
+        assert_eq!(file.version, 1);
+        assert!(file.field_errors.is_empty());
+
+        let file = decode(&serde_json::json!({"icy-index-metadata": true, "icy-version": 2, "icy-name": 42, "icy-logo": "//cdn/logo.png"}), &file_url);
+        assert!(file.index_metadata);
+        assert_eq!(file.version, 2);
+        assert_eq!(file.name.as_deref(), Some("42"));
+        assert_eq!(file.logo.as_deref(), Some("https://cdn/logo.png"));
+        assert!(file.field_errors.is_empty());
+
+        let file = decode(&serde_json::json!({"icy-index-metadata": 2, "icy-version": 0, "icy-logo": "ftp://x/logo.png"}), &file_url);
+        assert!(!file.index_metadata);
+        assert_eq!(file.version, 1);
+        assert_eq!(file.logo, None);
+        let fields: Vec<&str> = file.field_errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["icy-index-metadata", "icy-version", "icy-logo"]);
+    }
+
+    #[test]
+    fn cache_definitive_results() {
+        let cache = MetaInfoCache::new(Duration::MAX);
+        let results = [
+            (Ok(decode(&serde_json::json!({}), &Url::parse("http://x/streaminfo.json").unwrap())), true),
+            (Err(MetaInfoError::IllegalStatusCode(404)), true),
+            (Err(MetaInfoError::DecodeError()), true),
+            (Err(MetaInfoError::ConnectionFailed()), false),
+            (Err(MetaInfoError::TooManyRedirects()), false),
+            (Err(MetaInfoError::UrlParseError()), false),
+        ];
+        for (i, (result, cached)) in results.iter().enumerate() {
+            let homepage = format!("http://x/{}", i);
+            let _ = cache.get_or_fetch(&homepage, || result.clone());
+            let mut fetched = false;
+            let _ = cache.get_or_fetch(&homepage, || {
+                fetched = true;
+                result.clone()
+            });
+            assert_eq!(fetched, !cached, "{:?}", result);
+        }
+    }
+
+    fn stream_info(headers: Value) -> StreamInfo {
+        let mut json = serde_json::json!({"Type": "audio/mpeg", "CodecAudio": "MP3", "Hls": false, "IcyVersion": 1, "LanguageCodes": [], "SslError": false});
+        json.as_object_mut().unwrap().extend(headers.as_object().unwrap().clone());
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn file(json: Value) -> MetaInfoFile {
+        decode(&json, &Url::parse("http://x/streaminfo.json").unwrap())
+    }
+
+    fn conflicts(stream: &StreamInfo) -> Vec<(&str, &str, &str)> {
+        stream
+            .HeaderConflicts
+            .iter()
+            .map(|conflict| (conflict.field.as_str(), conflict.used_value.as_str(), conflict.ignored_value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_headers_first() {
+        let mut stream = stream_info(serde_json::json!({"Name": "Header Radio", "Genre": "rock"}));
+        file(serde_json::json!({"icy-name": "File Radio", "icy-description": "From file", "icy-genre": "rock"})).merge_into(&mut stream);
+
+        assert_eq!(stream.Name.as_deref(), Some("Header Radio"));
+        assert_eq!(stream.Description.as_deref(), Some("From file"));
+        assert_eq!(stream.Genre.as_deref(), Some("rock"));
+        assert_eq!(stream.OverrideIndexMetaData, Some(false));
+        assert_eq!(stream.FieldSources.get("Name"), Some(&FieldSource::Headers));
+        assert_eq!(stream.FieldSources.get("Description"), Some(&FieldSource::MetaInfoFile));
+        assert_eq!(stream.FieldSources.get("Genre"), Some(&FieldSource::Headers));
+        assert_eq!(stream.FieldSources.get("LogoUrl"), None);
+        assert_eq!(conflicts(&stream), vec![("Name", "Header Radio", "File Radio")]);
+    }
+
+    #[test]
+    fn merge_file_first() {
+        let mut stream = stream_info(serde_json::json!({"Name": "Header Radio", "Description": "From headers"}));
+        file(serde_json::json!({"icy-index-metadata": 1, "icy-name": "File Radio"})).merge_into(&mut stream);
+
+        assert_eq!(stream.Name.as_deref(), Some("File Radio"));
+        assert_eq!(stream.Description.as_deref(), Some("From headers"));
+        assert_eq!(stream.OverrideIndexMetaData, Some(true));
+        assert_eq!(stream.FieldSources.get("Name"), Some(&FieldSource::MetaInfoFile));
+        assert_eq!(stream.FieldSources.get("Description"), Some(&FieldSource::Headers));
+        assert_eq!(stream.FieldSources.get("OverrideIndexMetaData"), Some(&FieldSource::MetaInfoFile));
+        assert_eq!(conflicts(&stream), vec![("Name", "File Radio", "Header Radio")]);
+    }
+
+    #[test]
+    fn merge_index_metadata_of_headers() {
+        let mut stream = stream_info(serde_json::json!({"Name": "Header Radio", "OverrideIndexMetaData": true}));
+        file(serde_json::json!({"icy-index-metadata": 0, "icy-name": "File Radio"})).merge_into(&mut stream);
+
+        assert_eq!(stream.Name.as_deref(), Some("File Radio"));
+        assert_eq!(stream.OverrideIndexMetaData, Some(true));
+        assert_eq!(stream.FieldSources.get("Name"), Some(&FieldSource::MetaInfoFile));
+    }
+
+    #[test]
+    fn merge_language_codes() {
+        let mut stream = stream_info(serde_json::json!({"LanguageCodes": ["en"]}));
+        file(serde_json::json!({"icy-language-codes": "de, fr"})).merge_into(&mut stream);
+        assert_eq!(stream.LanguageCodes, vec!["en"]);
+        assert_eq!(stream.FieldSources.get("LanguageCodes"), Some(&FieldSource::Headers));
+        assert_eq!(conflicts(&stream), vec![("LanguageCodes", "en", "de,fr")]);
+
+        let mut stream = stream_info(serde_json::json!({}));
+        file(serde_json::json!({"icy-language-codes": "de, fr"})).merge_into(&mut stream);
+        assert_eq!(stream.LanguageCodes, vec!["de", "fr"]);
+        assert_eq!(stream.FieldSources.get("LanguageCodes"), Some(&FieldSource::MetaInfoFile));
+    }
+
+    #[test]
+    fn merge_lat_long() {
+        let invalid = stream_info(serde_json::json!({"GeoLatLong": {"Err": {"kind": "LatMissing", "field": "icy-geo-lat-long", "value": ""}}}));
+
+        let mut stream = invalid.clone();
+        file(serde_json::json!({"icy-geo-lat-long": "52.5,13.4"})).merge_into(&mut stream);
+        assert!(matches!(stream.GeoLatLong, Some(Ok(LatLong { lat, long })) if lat == 52.5 && long == 13.4));
+        assert_eq!(stream.FieldSources.get("GeoLatLong"), Some(&FieldSource::MetaInfoFile));
+
+        let mut stream = invalid.clone();
+        file(serde_json::json!({})).merge_into(&mut stream);
+        assert!(matches!(stream.GeoLatLong, Some(Err(_))));
+        assert_eq!(stream.FieldSources.get("GeoLatLong"), None);
+
+        let mut stream = invalid;
+        stream.GeoLatLong = Some(Ok(LatLong { lat: 1.0, long: 2.0 }));
+        file(serde_json::json!({"icy-geo-lat-long": "52.5,13.4"})).merge_into(&mut stream);
+        assert!(matches!(stream.GeoLatLong, Some(Ok(LatLong { lat, .. })) if lat == 1.0));
+        assert_eq!(stream.FieldSources.get("GeoLatLong"), Some(&FieldSource::Headers));
+    }
+}
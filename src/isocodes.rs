@@ -0,0 +1,134 @@
+use crate::DecodeError;
+use crate::DecodeErrorKind;
+
+use isocountry::CountryCode;
+use isolang::Language;
+
+/// ISO 639-2/B codes which differ from their ISO 639-2/T (and 639-3) counterparts
+const BIBLIOGRAPHIC_CODES: [(&str, &str); 20] = [
+    ("alb", "sqi"),
+    ("arm", "hye"),
+    ("baq", "eus"),
+    ("bur", "mya"),
+    ("chi", "zho"),
+    ("cze", "ces"),
+    ("dut", "nld"),
+    ("fre", "fra"),
+    ("geo", "kat"),
+    ("ger", "deu"),
+    ("gre", "ell"),
+    ("ice", "isl"),
+    ("mac", "mkd"),
+    ("mao", "mri"),
+    ("may", "msa"),
+    ("per", "fas"),
+    ("rum", "ron"),
+    ("slo", "slk"),
+    ("tib", "bod"),
+    ("wel", "cym"),
+];
+
+/// ISO 3166-1 country code as upper case alpha-2 code.
+/// Accepts alpha-2, alpha-3 and numeric codes in any case, and "UK" for Great Britain.
+pub fn country_code(value: &str) -> Result<String, DecodeError> {
+    alpha2(value.trim()).ok_or_else(|| DecodeError::new(DecodeErrorKind::InvalidCountryCode, value))
+}
+
+fn alpha2(value: &str) -> Option<String> {
+    let country = match value.len() {
+        2 if value.eq_ignore_ascii_case("uk") => Ok(CountryCode::GBR),
+        2 => CountryCode::for_alpha2_caseless(value),
+        3 if value.chars().all(|c| c.is_ascii_digit()) => CountryCode::for_id(value.parse().unwrap_or(0)),
+        3 => CountryCode::for_alpha3_caseless(value),
+        _ => return None,
+    };
+    country.map(|country| country.alpha2().to_string()).ok()
+}
+
+/// ISO 3166-2 subdivision code in upper case, e.g. "DE-BY".
+/// The country part must be a valid country, the subdivision part is only checked for its form
+/// (1 to 3 letters or digits).
+pub fn subdivision_code(value: &str) -> Result<String, DecodeError> {
+    let invalid = || DecodeError::new(DecodeErrorKind::InvalidSubdivisionCode, value);
+    let (country, subdivision) = value.trim().split_once(['-', '_']).ok_or_else(invalid)?;
+    let country = alpha2(country).ok_or_else(invalid)?;
+    let valid_subdivision = (1..=3).contains(&subdivision.len()) && subdivision.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid_subdivision {
+        return Err(invalid());
+    }
+    Ok(format!("{}-{}", country, subdivision.to_uppercase()))
+}
+
+/// ISO 639 language code as lower case ISO 639-1 code, or ISO 639-3 if the language has no
+/// two letter code. Accepts ISO 639-1, 639-2/T, 639-2/B and 639-3 codes, locales like "en-US"
+/// and English language names.
+pub fn language_code(value: &str) -> Result<String, DecodeError> {
+    let trimmed = value.trim();
+    let primary = trimmed.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    let language = match primary.len() {
+        2 => Language::from_639_1(&primary),
+        3 => {
+            let terminologic = BIBLIOGRAPHIC_CODES
+                .iter()
+                .find(|(bibliographic, _)| *bibliographic == primary)
+                .map(|(_, terminologic)| *terminologic)
+                .unwrap_or(&primary);
+            Language::from_639_3(terminologic)
+        }
+        _ => Language::from_name(&capitalize(trimmed)),
+    };
+    language
+        .map(|language| language.to_639_1().unwrap_or(language.to_639_3()).to_string())
+        .ok_or_else(|| DecodeError::new(DecodeErrorKind::InvalidLanguageCode, value))
+}
+
+fn capitalize(value: &str) -> String {
+    let lower = value.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => lower,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn country_codes() {
+        assert_eq!(country_code("DE").unwrap(), "DE");
+        assert_eq!(country_code(" de ").unwrap(), "DE");
+        assert_eq!(country_code("deu").unwrap(), "DE");
+        assert_eq!(country_code("276").unwrap(), "DE");
+        assert_eq!(country_code("UK").unwrap(), "GB");
+        for invalid in ["XX", "999", "Germany", ""].iter() {
+            assert!(matches!(country_code(invalid), Err(DecodeError { kind: DecodeErrorKind::InvalidCountryCode, .. })), "{}", invalid);
+        }
+        assert_eq!(country_code(" xx ").unwrap_err().value, " xx ");
+    }
+
+    #[test]
+    fn subdivision_codes() {
+        assert_eq!(subdivision_code("DE-BY").unwrap(), "DE-BY");
+        assert_eq!(subdivision_code("de_by").unwrap(), "DE-BY");
+        assert_eq!(subdivision_code("uk-eng").unwrap(), "GB-ENG");
+        assert_eq!(subdivision_code("FR-75").unwrap(), "FR-75");
+        for invalid in ["DE-BAYERN", "XX-BY", "DE-", "DEBY"].iter() {
+            assert!(matches!(subdivision_code(invalid), Err(DecodeError { kind: DecodeErrorKind::InvalidSubdivisionCode, .. })), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn language_codes() {
+        // ISO 639-1, locales, 639-2/T, 639-2/B and names all map to the two letter code
+        for value in ["de", "DE", "de-AT", "de_CH", "deu", "ger", "German", "german"].iter() {
+            assert_eq!(language_code(value).unwrap(), "de", "{}", value);
+        }
+        // no two letter code
+        assert_eq!(language_code("yue").unwrap(), "yue");
+        for invalid in ["xx", "123", ""].iter() {
+            assert!(matches!(language_code(invalid), Err(DecodeError { kind: DecodeErrorKind::InvalidLanguageCode, .. })), "{}", invalid);
+        }
+    }
+}
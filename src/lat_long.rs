@@ -1,18 +1,33 @@
 use crate::DecodeError;
+use crate::DecodeErrorKind;
 use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 
+/// Mean earth radius in kilometers
+const EARTH_RADIUS: f64 = 6371.0;
+
 /// Represents a geo-location with latitude and longitude. It can be
 /// constructed from a String.
-/// 
+///
+/// Accepted are decimal degrees separated by comma, semicolon or whitespace,
+/// degrees with minutes and seconds, and hemisphere letters before or after
+/// the numbers. Latitude has to be in -90..90 and longitude in -180..180.
+///
 /// # Example
 /// ```rust
 /// use std::convert::TryFrom;
 /// use av_stream_info_rust::LatLong;
-/// 
+///
 /// let lat_long_str = String::from("10.1,-3.1");
 /// let lat_long = LatLong::try_from(lat_long_str).unwrap();
 /// println!("{},{}", lat_long.lat, lat_long.long);
+///
+/// let munich: LatLong = "48°8'15\" N, 11°34'30\" E".parse().unwrap();
+/// let berlin: LatLong = "52.52 N; 13.405 E".parse().unwrap();
+/// assert!((munich.distance(&berlin) - 504.0).abs() < 1.0);
+/// assert!("91,200".parse::<LatLong>().is_err());
 /// ```
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LatLong {
@@ -22,24 +37,215 @@ pub struct LatLong {
     pub long: f64,
 }
 
+impl LatLong {
+    /// Great circle distance to another location in kilometers
+    pub fn distance(&self, other: &LatLong) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let delta_lat = lat2 - lat1;
+        let delta_long = (other.long - self.long).to_radians();
+        let a = (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_long / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+}
+
 impl TryFrom<String> for LatLong {
     type Error = DecodeError;
 
     fn try_from(
         lat_long_str: String,
     ) -> std::result::Result<Self, <Self as TryFrom<String>>::Error> {
-        let mut iter = lat_long_str.splitn(2, ",");
-        Ok(LatLong {
-            lat: iter
-                .next()
-                .ok_or(DecodeError::LatMissing)?
-                .parse()
-                .or(Err(DecodeError::NumberParseError))?,
-            long: iter
-                .next()
-                .ok_or(DecodeError::LongMissing)?
-                .parse()
-                .or(Err(DecodeError::NumberParseError))?,
-        })
+        lat_long_str.parse()
+    }
+}
+
+impl FromStr for LatLong {
+    type Err = DecodeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse(value).map_err(|kind| DecodeError::new(kind, value))
+    }
+}
+
+fn parse(value: &str) -> Result<LatLong, DecodeErrorKind> {
+    let (first, second) = split(value.trim())?;
+    let first = Coordinate::parse(first)?;
+    let second = Coordinate::parse(second)?;
+    let (lat, long) = match (first.axis, second.axis) {
+        (Axis::Long, Axis::Lat) => (second.value, first.value),
+        (Axis::Lat, Axis::Lat) | (Axis::Long, Axis::Long) => return Err(DecodeErrorKind::NumberParseError),
+        _ => (first.value, second.value),
+    };
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(DecodeErrorKind::LatOutOfRange);
+    }
+    if !(-180.0..=180.0).contains(&long) {
+        return Err(DecodeErrorKind::LongOutOfRange);
+    }
+    Ok(LatLong { lat, long })
+}
+
+impl fmt::Display for LatLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.lat, self.long)
+    }
+}
+
+/// Split into latitude and longitude part
+fn split(value: &str) -> Result<(&str, &str), DecodeErrorKind> {
+    if value.is_empty() {
+        return Err(DecodeErrorKind::LatMissing);
+    }
+    let parts = if value.contains(';') {
+        value.split_once(';')
+    } else if value.matches(',').count() == 1 {
+        value.split_once(',')
+    } else if value.contains(", ") {
+        // decimal commas, "48,1, 11,5"
+        value.split_once(", ")
+    } else if let Some(index) = hemisphere_split(value) {
+        Some(value.split_at(index))
+    } else {
+        let mut words = value.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(first), Some(second), None) => Some((first, second)),
+            _ => None,
+        }
+    };
+    let (first, second) = parts.ok_or(DecodeErrorKind::LongMissing)?;
+    if second.trim().is_empty() {
+        return Err(DecodeErrorKind::LongMissing);
+    }
+    Ok((first, second))
+}
+
+/// Split position for "48.1 N 11.5 E" and "N 48.1 E 11.5"
+fn hemisphere_split(value: &str) -> Option<usize> {
+    let mut letters = value.char_indices().filter(|(_, c)| is_hemisphere(*c));
+    let (first, letter) = letters.next()?;
+    if value[..first].trim().is_empty() {
+        letters.next().map(|(second, _)| second)
+    } else {
+        Some(first + letter.len_utf8())
+    }
+}
+
+fn is_hemisphere(c: char) -> bool {
+    matches!(c.to_ascii_uppercase(), 'N' | 'S' | 'E' | 'W')
+}
+
+enum Axis {
+    Lat,
+    Long,
+    Unknown,
+}
+
+struct Coordinate {
+    axis: Axis,
+    value: f64,
+}
+
+impl Coordinate {
+    /// Decimal degrees or degrees, minutes and seconds with optional hemisphere letter
+    fn parse(value: &str) -> Result<Coordinate, DecodeErrorKind> {
+        let value = value.trim();
+        let (hemisphere, number) = match (value.chars().next(), value.chars().last()) {
+            (Some(c), _) if is_hemisphere(c) => (Some(c.to_ascii_uppercase()), &value[c.len_utf8()..]),
+            (_, Some(c)) if is_hemisphere(c) => (Some(c.to_ascii_uppercase()), &value[..value.len() - c.len_utf8()]),
+            _ => (None, value),
+        };
+        let degrees = parse_degrees(number)?;
+        let (axis, value) = match hemisphere {
+            Some(_) if degrees < 0.0 => return Err(DecodeErrorKind::NumberParseError),
+            Some('N') => (Axis::Lat, degrees),
+            Some('S') => (Axis::Lat, -degrees),
+            Some('E') => (Axis::Long, degrees),
+            Some(_) => (Axis::Long, -degrees),
+            None => (Axis::Unknown, degrees),
+        };
+        Ok(Coordinate { axis, value })
+    }
+}
+
+/// "48.1", "48,1", "48°8'15\"" or "48 8 15"
+fn parse_degrees(value: &str) -> Result<f64, DecodeErrorKind> {
+    let numbers: Vec<f64> = value
+        .split(|c: char| c.is_whitespace() || matches!(c, '°' | '\'' | '"' | '′' | '″' | 'º'))
+        .filter(|part| !part.is_empty())
+        .map(|part| part.replace(',', ".").parse::<f64>())
+        .collect::<Result<_, _>>()
+        .or(Err(DecodeErrorKind::NumberParseError))?;
+    let (degrees, minutes, seconds) = match numbers.as_slice() {
+        [degrees] => (*degrees, 0.0, 0.0),
+        [degrees, minutes] => (*degrees, *minutes, 0.0),
+        [degrees, minutes, seconds] => (*degrees, *minutes, *seconds),
+        _ => return Err(DecodeErrorKind::NumberParseError),
+    };
+    let valid_part = |part: f64| (0.0..60.0).contains(&part);
+    if !degrees.is_finite() || !valid_part(minutes) || !valid_part(seconds) {
+        return Err(DecodeErrorKind::NumberParseError);
+    }
+    let magnitude = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    Ok(if value.trim_start().starts_with('-') { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_lat_long(value: &str, lat: f64, long: f64) {
+        let lat_long: LatLong = value.parse().unwrap_or_else(|err| panic!("{}: {:?}", value, err));
+        assert!((lat_long.lat - lat).abs() < 1e-9 && (lat_long.long - long).abs() < 1e-9, "{}: {}", value, lat_long);
+    }
+
+    #[test]
+    fn decimal_degrees() {
+        assert_lat_long("48.1,11.5", 48.1, 11.5);
+        assert_lat_long(" 48.1 ; 11.5 ", 48.1, 11.5);
+        assert_lat_long("48.1 11.5", 48.1, 11.5);
+        assert_lat_long("-33.9,-70.7", -33.9, -70.7);
+    }
+
+    #[test]
+    fn decimal_commas() {
+        // a single comma separates, it is not a decimal comma
+        assert_lat_long("-0,5", 0.0, 5.0);
+        assert_lat_long("48,1, 11,5", 48.1, 11.5);
+        assert_lat_long("48,1 11,5", 48.1, 11.5);
+        assert_lat_long("48,1;11,5", 48.1, 11.5);
+        // can not be split unambiguously
+        assert_eq!("48,1,11,5".parse::<LatLong>().unwrap_err().kind, DecodeErrorKind::LongMissing);
+    }
+
+    #[test]
+    fn hemispheres_and_minutes() {
+        assert_lat_long("48.1 N 11.5 E", 48.1, 11.5);
+        assert_lat_long("N 48.1 E 11.5", 48.1, 11.5);
+        assert_lat_long("48.1S, 11.5W", -48.1, -11.5);
+        assert_lat_long("11.5 E, 48.1 N", 48.1, 11.5);
+        assert_lat_long("48°8'15\" N, 11°34'30\" E", 48.1375, 11.575);
+        assert_lat_long("48 8 15, 11 34 30", 48.1375, 11.575);
+        assert_lat_long("-48°30', 0", -48.5, 0.0);
+    }
+
+    #[test]
+    fn invalid() {
+        let errors = [
+            ("", DecodeErrorKind::LatMissing),
+            ("48.1", DecodeErrorKind::LongMissing),
+            ("48.1,", DecodeErrorKind::LongMissing),
+            ("abc,def", DecodeErrorKind::NumberParseError),
+            ("N 48, N 11", DecodeErrorKind::NumberParseError),
+            ("-48.1 N, 11.5 E", DecodeErrorKind::NumberParseError),
+            ("48 60 0, 11", DecodeErrorKind::NumberParseError),
+            ("inf, 0", DecodeErrorKind::NumberParseError),
+            ("91,200", DecodeErrorKind::LatOutOfRange),
+            ("90,180.5", DecodeErrorKind::LongOutOfRange),
+            ("11.5 E, 95 N", DecodeErrorKind::LatOutOfRange),
+        ];
+        for (value, expected) in errors.iter() {
+            let err = value.parse::<LatLong>().unwrap_err();
+            assert_eq!(err.kind, *expected, "{}", value);
+            assert_eq!(err.value, *value);
+        }
     }
 }
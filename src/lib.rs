@@ -3,19 +3,22 @@
 //! from its master playlist file.
 //!
 //! # Example
-//! ```rust
-//! let list = av_stream_info_rust::check("https://example.com/test.m3u", 10, 3, 3);
-//! for item in list {
-//!     println!("{:?}", item);
-//! }
+//! ```no_run
+//! let item = av_stream_info_rust::check_tree("https://example.com/test.m3u", 10, 3, 3, true);
+//! println!("{:?}", item);
 //! ```
 
+extern crate chardetng;
+extern crate encoding_rs;
 extern crate hls_m3u8;
+extern crate isocountry;
+extern crate isolang;
 #[macro_use]
 extern crate log;
 extern crate native_tls;
+extern crate percent_encoding;
 extern crate playlist_decoder;
-extern crate reqwest;
+extern crate quick_xml;
 extern crate url;
 
 extern crate serde;
@@ -23,28 +26,89 @@ extern crate serde_json;
 
 //extern crate tree_magic;
 
+mod audioparameters;
+mod cancellation;
+mod charset;
+mod checkcontext;
+mod checkevent;
+mod checkoptions;
+mod codec;
 mod decodeerror;
+#[cfg(feature = "loudness")]
+mod ebur128;
+mod fieldsource;
+mod headerconflict;
+mod headerdialect;
+mod htmllinks;
+mod isocodes;
 mod lat_long;
+mod loudness;
+mod mountdiscovery;
+mod playlistformat;
+mod playlistmetadata;
+mod redirect;
 mod request;
+mod resulttree;
+mod selection;
+mod serverstatus;
 mod streamcheck;
 mod streamcheckerror;
 mod streamcheckresult;
 mod streamdeepscan;
 mod streaminfo;
+mod streamrecord;
 
 mod http_config;
 
-use std::thread;
+use checkcontext::CheckContext;
+use std::fs;
+use std::path::Path;
 use std::time::Duration;
 
+pub use audioparameters::AudioParameters;
+pub use cancellation::CancellationToken;
+pub use checkevent::CheckEvent;
+pub use checkoptions::CheckOptions;
+pub use codec::AacProfile;
+pub use codec::AudioCodec;
+pub use codec::Container;
+pub use codec::H264Profile;
+pub use codec::VideoCodec;
 pub use decodeerror::DecodeError;
+pub use decodeerror::DecodeErrorKind;
+pub use fieldsource::FieldSource;
 pub use http_config::extract_from_homepage;
+pub use http_config::fetch_meta_info_file;
+pub use http_config::MetaInfoCache;
+pub use http_config::MetaInfoError;
+pub use http_config::MetaInfoFieldError;
 pub use http_config::MetaInfoFile;
+pub use headerconflict::HeaderConflict;
+pub use headerdialect::HeaderDialect;
 pub use lat_long::LatLong;
+pub use loudness::Loudness;
+pub use loudness::LoudnessError;
+pub use mountdiscovery::discover_mounts;
+pub use playlistformat::PlaylistFormat;
+pub use playlistmetadata::PlaylistMetadata;
+pub use redirect::Redirect;
+pub use resulttree::FoundError;
+pub use resulttree::FoundStream;
+pub use resulttree::Nodes;
+pub use resulttree::ResultNode;
+pub use selection::select_best;
+pub use selection::Selection;
+pub use selection::SelectionReason;
+pub use selection::StreamPreferences;
+pub use serverstatus::fetch_server_status;
+pub use serverstatus::ServerStatus;
+pub use serverstatus::ServerStatusError;
+pub use serverstatus::ServerType;
 pub use streamcheckerror::StreamCheckError;
 pub use streamcheckresult::StreamCheckResult;
 pub use streamcheckresult::UrlType;
 pub use streaminfo::StreamInfo;
+pub use streamrecord::StreamRecord;
 
 /// Check url for audio/video stream.
 /// # Example
@@ -58,35 +122,95 @@ pub use streaminfo::StreamInfo;
 /// * `max_depth` - How many layers of http redirects or playlists should be followed
 /// * `retries` - Retry how many times to find at least one working stream
 /// * `early_exit_on_first_ok` - return from checking as early as 1 working stream has been found
-pub fn check_tree(url: &str, timeout: u32, max_depth: u8, mut retries: u8, early_exit_on_first_ok: bool) -> StreamCheckResult {
+pub fn check_tree(url: &str, timeout: u32, max_depth: u8, retries: u8, early_exit_on_first_ok: bool) -> StreamCheckResult {
+    let options = CheckOptions {
+        timeout,
+        max_depth,
+        retries,
+        early_exit_on_first_ok,
+        ..CheckOptions::default()
+    };
+    check_tree_with_options(url, &options)
+}
+
+/// Check url for audio/video stream with additional options.
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::CheckOptions;
+///
+/// let options = CheckOptions {
+///     loudness_seconds: Some(10),
+///     ..CheckOptions::default()
+/// };
+/// let item = av_stream_info_rust::check_tree_with_options("https://example.com/test.m3u", &options);
+/// println!("{:#?}", item);
+/// ```
+pub fn check_tree_with_options(url: &str, options: &CheckOptions) -> StreamCheckResult {
+    check_tree_internal(url, &CheckContext::new(options, None))
+}
+
+/// Check url for audio/video stream and report progress to an observer.
+/// The observer is called for every event while checking, e.g. for followed
+/// redirects, decoded playlists, found streams and errors.
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{CheckEvent, CheckOptions};
+///
+/// let observer = |event: &CheckEvent| println!("{:?}", event);
+/// let item = av_stream_info_rust::check_tree_with_observer("https://example.com/test.m3u", &CheckOptions::default(), &observer);
+/// println!("{:#?}", item);
+/// ```
+pub fn check_tree_with_observer(url: &str, options: &CheckOptions, observer: &(dyn Fn(&CheckEvent) + Sync)) -> StreamCheckResult {
+    check_tree_internal(url, &CheckContext::new(options, Some(observer)))
+}
+
+fn check_tree_internal(url: &str, ctx: &CheckContext) -> StreamCheckResult {
+    with_retries(url, ctx, || streamcheck::check(url, ctx, ctx.options.max_depth, &[]))
+}
+
+/// Check playlist content, e.g. uploaded by a user, with the same decoding and checks
+/// of the entries as a downloaded playlist. Relative entries are resolved against `base_url`.
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::CheckOptions;
+///
+/// let content = "#EXTM3U\n#EXTINF:-1,My Radio\n/live.mp3\n";
+/// let item = av_stream_info_rust::check_playlist_content(content, "https://example.com/", &CheckOptions::default());
+/// println!("{:#?}", item);
+/// ```
+pub fn check_playlist_content(content: &str, base_url: &str, options: &CheckOptions) -> StreamCheckResult {
+    let ctx = CheckContext::new(options, None);
+    with_retries(base_url, &ctx, || streamcheck::check_content(content, base_url, &ctx, options.max_depth))
+}
+
+/// Check a local playlist file like [`check_playlist_content`].
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::CheckOptions;
+///
+/// let item = av_stream_info_rust::check_playlist_file("upload.pls", "https://example.com/", &CheckOptions::default());
+/// println!("{:#?}", item);
+/// ```
+pub fn check_playlist_file<P: AsRef<Path>>(path: P, base_url: &str, options: &CheckOptions) -> StreamCheckResult {
+    match fs::read(path) {
+        Ok(bytes) => check_playlist_content(&charset::decode(&bytes, None), base_url, options),
+        Err(_err) => StreamCheckResult::new(base_url, Err(StreamCheckError::PlaylistReadFailed())),
+    }
+}
+
+fn with_retries<F: Fn() -> StreamCheckResult>(url: &str, ctx: &CheckContext, check: F) -> StreamCheckResult {
+    let mut retries = ctx.options.retries;
     loop {
-        let result = streamcheck::check(url, early_exit_on_first_ok, timeout, max_depth);
-        if has_ok_result_recursive(&result) {
+        let result = check();
+        if result.first_ok_stream().is_some() {
             return result;
         }
-        if retries == 0 {
+        if retries == 0 || ctx.interrupted().is_some() {
             return result;
         }
 
         retries -= 1;
-        thread::sleep(Duration::from_secs(1));
-    }
-}
-
-fn has_ok_result_recursive(result: &StreamCheckResult) -> bool {
-    match &result.info {
-        Ok(info) => match info {
-            UrlType::Stream(_) => true,
-            UrlType::Redirect(item) => has_ok_result_recursive(item),
-            UrlType::PlayList(list) => {
-                for item in list {
-                    if has_ok_result_recursive(item) {
-                        return true;
-                    }
-                }
-                false
-            }
-        },
-        Err(_) => false,
+        ctx.emit(|| CheckEvent::Retry { url: url.to_string(), retries_left: retries });
+        ctx.sleep(Duration::from_secs(1));
     }
 }
@@ -0,0 +1,117 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cancellation::CancellationToken;
+use crate::request::ContentReader;
+
+#[cfg(feature = "loudness")]
+use crate::ebur128::Meter;
+
+/// Loudness of a stream measured according to EBU R128
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS, None if the analyzed audio was silent
+    pub integrated: Option<f64>,
+    /// Loudness range in LU, None if less than 3 seconds of audio were analyzed
+    pub range: Option<f64>,
+    /// Maximum true peak of all channels in dBTP, None if the analyzed audio was silent
+    pub true_peak: Option<f64>,
+    /// Duration of the analyzed audio in seconds
+    pub duration: f64,
+}
+
+/// Posible errors for loudness measurement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LoudnessError {
+    NotSupported(),
+    UnknownFormat(),
+    DecoderFailed(),
+    NoAudio(),
+}
+
+impl fmt::Display for LoudnessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoudnessError::NotSupported() => write!(f, "NotSupported()"),
+            LoudnessError::UnknownFormat() => write!(f, "UnknownFormat()"),
+            LoudnessError::DecoderFailed() => write!(f, "DecoderFailed()"),
+            LoudnessError::NoAudio() => write!(f, "NoAudio()"),
+        }
+    }
+}
+
+impl Error for LoudnessError {}
+
+/// Decode up to `seconds` of audio from the reader and measure its loudness,
+/// stops early with the audio decoded so far if the check is cancelled
+#[cfg(feature = "loudness")]
+pub fn measure(reader: ContentReader, mime: &str, seconds: u32, cancellation: &CancellationToken) -> Result<Loudness, LoudnessError> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::errors::Error as SymphoniaError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::{MediaSourceStream, ReadOnlySource};
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let source = MediaSourceStream::new(Box::new(ReadOnlySource::new(reader)), Default::default());
+    let mut hint = Hint::new();
+    hint.mime_type(mime);
+    let probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .or(Err(LoudnessError::UnknownFormat()))?;
+    let mut format = probed.format;
+    let track = format.default_track().ok_or(LoudnessError::NoAudio())?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .or(Err(LoudnessError::NotSupported()))?;
+
+    let mut meter: Option<Meter> = None;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    // stops at the end of the stream, on read timeout or when the connection is closed on cancel
+    while let Ok(packet) = format.next_packet() {
+        if cancellation.is_cancelled() {
+            break;
+        }
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(_) => break,
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        let meter = meter.get_or_insert_with(|| Meter::new(spec.rate, channels));
+        if meter.rate != spec.rate || meter.channels != channels {
+            trace!("ignore audio with changed signal spec: {:?}", spec);
+            continue;
+        }
+        if buffer.as_ref().map(|b| b.capacity() < decoded.capacity() * channels).unwrap_or(true) {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        if let Some(buffer) = buffer.as_mut() {
+            buffer.copy_interleaved_ref(decoded);
+            meter.add(buffer.samples());
+        }
+        if meter.duration() >= seconds as f64 {
+            break;
+        }
+    }
+
+    match meter {
+        Some(meter) if meter.frames > 0 => Ok(meter.result()),
+        Some(_) => Err(LoudnessError::NoAudio()),
+        None => Err(LoudnessError::DecoderFailed()),
+    }
+}
+
+/// Decode up to `seconds` of audio from the reader and measure its loudness
+#[cfg(not(feature = "loudness"))]
+pub fn measure(_reader: ContentReader, _mime: &str, _seconds: u32, _cancellation: &CancellationToken) -> Result<Loudness, LoudnessError> {
+    Err(LoudnessError::NotSupported())
+}
@@ -0,0 +1,123 @@
+use crate::checkcontext::CheckContext;
+use crate::serverstatus;
+use crate::streamcheck;
+use crate::CheckOptions;
+use crate::ServerStatusError;
+use crate::StreamCheckResult;
+
+/// Find other mount points of the same station on the Icecast or Shoutcast server of a
+/// checked stream. Every mount point listed on the status page of the server is checked,
+/// only working streams with the same station name as the original stream are returned.
+/// Quality descriptions at the end of the name like "(AAC 64k)" are ignored.
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::CheckOptions;
+///
+/// let options = CheckOptions::default();
+/// let item = av_stream_info_rust::check_tree_with_options("http://example.com:8000/mp3-128", &options);
+/// let alternatives = av_stream_info_rust::discover_mounts(&item, &options);
+/// println!("{:#?}", alternatives);
+/// ```
+pub fn discover_mounts(result: &StreamCheckResult, options: &CheckOptions) -> Result<Vec<StreamCheckResult>, ServerStatusError> {
+    let found = result.first_ok_stream().ok_or(ServerStatusError::NoWorkingStream())?;
+    let (url, stream) = (found.url, found.info);
+    let ctx = CheckContext::new(options, None);
+    let status = match &stream.ServerStatus {
+        Some(Ok(status)) => status.clone(),
+        _ => {
+            let server_type = serverstatus::detect_server_type(stream).ok_or(ServerStatusError::UnknownServer())?;
+            serverstatus::fetch(url, server_type, ctx.timeout(), Some(&ctx.cancellation))?
+        }
+    };
+
+    let station = stream.Name.as_deref().map(station_name);
+    let mut list = vec![];
+    for mount in status.mounts {
+        if ctx.interrupted().is_some() {
+            break;
+        }
+        debug!("check mount '{}' of '{}'", mount, url);
+        let mount_result = streamcheck::check(&mount, &ctx, options.max_depth, &[]);
+        let same_station = match (mount_result.first_ok_stream(), &station) {
+            (Some(mount_stream), Some(station)) => mount_stream.info.Name.as_deref().map(station_name).as_ref() == Some(station),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if same_station {
+            list.push(mount_result);
+        }
+    }
+    Ok(list)
+}
+
+/// Station name without a trailing quality description, e.g. "Radio X (AAC 64k)",
+/// "Radio X - MP3 128 kbps" and "Radio X HQ" are all "radio x"
+fn station_name(name: &str) -> String {
+    let mut name = name.trim().to_lowercase();
+    loop {
+        let trimmed = name.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '|' | '/' | ':' | ',' | '·'));
+        let stripped = strip_bracket_suffix(trimmed).or_else(|| strip_quality_suffix(trimmed));
+        match stripped {
+            Some(stripped) if !stripped.trim().is_empty() => name = stripped.to_string(),
+            _ => return trimmed.to_string(),
+        }
+    }
+}
+
+/// "radio x (aac 64k)" -> "radio x "
+fn strip_bracket_suffix(name: &str) -> Option<&str> {
+    let open = match name.chars().last()? {
+        ')' => '(',
+        ']' => '[',
+        _ => return None,
+    };
+    name.rfind(open).map(|index| &name[..index])
+}
+
+/// "radio x mp3 128k" -> "radio x mp3 ", "radio x 128 kbps" -> "radio x "
+fn strip_quality_suffix(name: &str) -> Option<&str> {
+    let index = name.rfind(char::is_whitespace).map(|index| index + 1).unwrap_or(0);
+    let word = &name[index..];
+    let is_codec = matches!(word, "mp3" | "aac" | "aac+" | "he-aac" | "ogg" | "vorbis" | "opus" | "flac" | "hq" | "lq");
+    let number = word.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '/');
+    let is_bitrate = !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+        && matches!(&word[number.len()..], "k" | "kb" | "kbps" | "kbit" | "kbit/s" | "kb/s");
+    if is_codec || is_bitrate {
+        return Some(&name[..index]);
+    }
+    if matches!(word, "kbps" | "kbit" | "kbit/s" | "kb/s") {
+        // unit as separate word, drop the number in front of it as well
+        let rest = name[..index].trim_end();
+        let number_index = rest.rfind(char::is_whitespace).map(|index| index + 1).unwrap_or(0);
+        if rest[number_index..].chars().all(|c| c.is_ascii_digit()) {
+            return Some(&rest[..number_index]);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::station_name;
+
+    #[test]
+    fn same_station_names() {
+        let names = [
+            ("Radio X", "radio x"),
+            ("Radio X (AAC 64k)", "radio x"),
+            ("Radio X [128 kbps]", "radio x"),
+            ("Radio X - MP3 128 kbps", "radio x"),
+            ("Radio X | aac+ 48k", "radio x"),
+            ("Radio X HQ", "radio x"),
+            ("Radio 1", "radio 1"),
+            ("Radio X - Rock", "radio x - rock"),
+            ("128k", "128k"),
+            ("(Radio X)", "(radio x)"),
+        ];
+        for (name, expected) in names.iter() {
+            assert_eq!(station_name(name), *expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+
+/// Format of a playlist, detected from its content or its content type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaylistFormat {
+    M3u,
+    /// HLS master or media playlist (M3U with "#EXT-X-" tags)
+    Hls,
+    Pls,
+    Asx,
+    Xspf,
+    /// MPEG-DASH manifest
+    Mpd,
+    /// Web page with links to streams, see [`CheckOptions::html_links`](crate::CheckOptions::html_links)
+    Html,
+}
+
+/// Detect playlist format from the first bytes of a response body.
+/// Binary content (containing zero bytes) is never detected as a playlist,
+/// unless a byte order mark marks it as UTF-16 text.
+pub fn sniff(bytes: &[u8]) -> Option<PlaylistFormat> {
+    let text = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_length)) => encoding.decode_without_bom_handling(&bytes[bom_length..]).0,
+        None if bytes.contains(&0) => return None,
+        None => String::from_utf8_lossy(bytes),
+    };
+    let text = text.to_lowercase();
+    let text = text.trim_start();
+
+    if text.starts_with("#extm3u") || text.starts_with("#ext-x-") {
+        if text.contains("#ext-x-") {
+            Some(PlaylistFormat::Hls)
+        } else {
+            Some(PlaylistFormat::M3u)
+        }
+    } else if text.starts_with("[playlist]") {
+        Some(PlaylistFormat::Pls)
+    } else if text.starts_with('<') {
+        if text.contains("<asx") {
+            Some(PlaylistFormat::Asx)
+        } else if text.contains("<playlist") && (text.contains("xspf") || text.contains("<tracklist")) {
+            // the xspf namespace is sometimes left out
+            Some(PlaylistFormat::Xspf)
+        } else if text.contains("<mpd") {
+            Some(PlaylistFormat::Mpd)
+        } else {
+            None
+        }
+    } else {
+        // plain M3U without header, only urls and comments
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .peekable();
+        if lines.peek().is_some() && lines.all(|line| line.contains("://") && !line.contains(' ')) {
+            Some(PlaylistFormat::M3u)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_formats() {
+        let cases: Vec<(&[u8], Option<PlaylistFormat>)> = vec![
+            (b"#EXTM3U\n#EXTINF:-1,Radio\nhttp://example.com/live.mp3\n", Some(PlaylistFormat::M3u)),
+            (b"\xef\xbb\xbf  #extm3u\r\nlive.mp3\r\n", Some(PlaylistFormat::M3u)),
+            (b"#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=128000\nlow.m3u8\n", Some(PlaylistFormat::Hls)),
+            (b"#EXT-X-VERSION:3\n#EXTINF:10,\nsegment.ts\n", Some(PlaylistFormat::Hls)),
+            // text/plain M3U without header
+            (b"http://example.com/live.mp3\nhttps://example.com/live.aac\n", Some(PlaylistFormat::M3u)),
+            (b"# comment\n\nhttp://example.com/live.mp3\n", Some(PlaylistFormat::M3u)),
+            (b"live.mp3\n", None),
+            (b"Welcome to http://example.com/\n", None),
+            // PLS without NumberOfEntries and Version
+            (b"[playlist]\nFile1=http://example.com/live.mp3\n", Some(PlaylistFormat::Pls)),
+            (b"[Playlist]\r\nNumberOfEntries=1\r\nFile1=http://example.com/live.mp3\r\n", Some(PlaylistFormat::Pls)),
+            (b"<ASX version=\"3.0\"><ENTRY><REF HREF=\"live.mp3\"/></ENTRY></ASX>", Some(PlaylistFormat::Asx)),
+            (b"<?xml version=\"1.0\"?><playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\"><trackList/></playlist>", Some(PlaylistFormat::Xspf)),
+            (b"<playlist><trackList><track><identifier>live.mp3</identifier></track></trackList></playlist>", Some(PlaylistFormat::Xspf)),
+            (b"<?xml version=\"1.0\"?><MPD xmlns=\"urn:mpeg:dash:schema:mpd:2011\"></MPD>", Some(PlaylistFormat::Mpd)),
+            (b"<!DOCTYPE html><html><body><audio src=\"live.mp3\"></audio></body></html>", None),
+            (b"\xff\xfe#\x00E\x00X\x00T\x00M\x003\x00U\x00\n\x00l\x00i\x00v\x00e\x00.\x00m\x00p\x003\x00", Some(PlaylistFormat::M3u)),
+            (b"\xfe\xff\x00[\x00p\x00l\x00a\x00y\x00l\x00i\x00s\x00t\x00]", Some(PlaylistFormat::Pls)),
+            (b"\xff\xfb\x90\x00\x00\x00", None),
+            (b"", None),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(sniff(bytes), expected, "{}", String::from_utf8_lossy(bytes));
+        }
+    }
+}
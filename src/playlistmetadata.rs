@@ -0,0 +1,299 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Information about an entry of a playlist besides its url
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistMetadata {
+    /// Title of the entry (M3U "#EXTINF", PLS "TitleN", XSPF and ASX "title"),
+    /// falls back to the title of the whole playlist
+    pub title: Option<String>,
+    /// Duration in seconds (M3U "#EXTINF", PLS "LengthN", XSPF "duration"),
+    /// None for endless streams
+    pub duration: Option<u64>,
+    /// XSPF "creator" or ASX "author"
+    pub creator: Option<String>,
+    /// XSPF "image"
+    pub image: Option<String>,
+}
+
+impl PlaylistMetadata {
+    fn is_empty(&self) -> bool {
+        self == &PlaylistMetadata::default()
+    }
+}
+
+/// Playlist entry while decoding, may be known by multiple urls (XSPF location and identifier)
+#[derive(Default)]
+struct Entry {
+    urls: Vec<String>,
+    metadata: PlaylistMetadata,
+}
+
+/// Extract metadata of the entries of a playlist. The result maps the urls as written
+/// in the playlist to their metadata, entries without metadata are left out.
+pub fn decode(content: &str) -> HashMap<String, PlaylistMetadata> {
+    let content_small = content.to_lowercase();
+    let (list_title, entries) = if content_small.contains("<playlist") {
+        decode_xml(content, "playlist", "playlist/tracklist/track")
+    } else if content_small.contains("<asx") {
+        decode_xml(content, "asx", "asx/entry")
+    } else if content_small.contains("[playlist]") {
+        (None, decode_pls(content))
+    } else {
+        decode_m3u(content)
+    };
+
+    let mut map = HashMap::new();
+    for mut entry in entries {
+        if entry.metadata.title.is_none() {
+            entry.metadata.title = list_title.clone();
+        }
+        if entry.metadata.is_empty() {
+            continue;
+        }
+        for url in entry.urls {
+            map.insert(url, entry.metadata.clone());
+        }
+    }
+    map
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Durations below zero mean "endless"
+fn duration_seconds(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds > 0.0)
+        .map(|seconds| seconds.round() as u64)
+}
+
+/// "#EXTINF:duration [attributes],title" describes the next url,
+/// "#PLAYLIST:title" is the title of the whole list
+fn decode_m3u(content: &str) -> (Option<String>, Vec<Entry>) {
+    let mut list_title = None;
+    let mut entries = vec![];
+    let mut metadata = PlaylistMetadata::default();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = match title_separator(info) {
+                Some(index) => (&info[..index], &info[index + 1..]),
+                None => (info, ""),
+            };
+            // attributes like tvg-logo="..." may follow the duration
+            let duration = duration.split_whitespace().next().unwrap_or("");
+            metadata = PlaylistMetadata {
+                title: non_empty(title),
+                duration: duration_seconds(duration),
+                ..PlaylistMetadata::default()
+            };
+        } else if let Some(title) = line.strip_prefix("#PLAYLIST:") {
+            list_title = non_empty(title);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            entries.push(Entry {
+                urls: vec![line.to_string()],
+                metadata: std::mem::take(&mut metadata),
+            });
+        }
+    }
+    (list_title, entries)
+}
+
+/// Position of the comma before the title, commas in quoted attribute values are skipped
+fn title_separator(info: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (index, c) in info.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// "FileN", "TitleN" and "LengthN" with the same number belong together
+fn decode_pls(content: &str) -> Vec<Entry> {
+    let mut entries: HashMap<u32, Entry> = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let (key, value) = match line.find('=') {
+            Some(index) => (line[..index].trim().to_lowercase(), line[index + 1..].trim()),
+            None => continue,
+        };
+        for (prefix, field) in [("file", 0), ("title", 1), ("length", 2)].iter() {
+            if let Some(id) = key.strip_prefix(prefix).and_then(|id| id.parse::<u32>().ok()) {
+                let entry = entries.entry(id).or_default();
+                match field {
+                    0 => entry.urls.push(value.to_string()),
+                    1 => entry.metadata.title = non_empty(value),
+                    _ => entry.metadata.duration = duration_seconds(value),
+                }
+            }
+        }
+    }
+    entries.into_values().collect()
+}
+
+fn name_of(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).to_lowercase()
+}
+
+/// Url of an ASX <ref href="..."/> element
+fn ref_href(element: &BytesStart) -> Option<String> {
+    if name_of(element) != "ref" {
+        return None;
+    }
+    element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.local_name().as_ref().eq_ignore_ascii_case(b"href"))
+        .and_then(|attribute| attribute.unescape_value().ok().map(|value| value.trim().to_string()))
+}
+
+/// XSPF and ASX are decoded alike: the entry element contains title, creator or author,
+/// image, duration and the url as location or identifier text or as href of a ref element
+fn decode_xml(content: &str, root: &str, entry_path: &str) -> (Option<String>, Vec<Entry>) {
+    let list_title_path = format!("{}/title", root);
+    let mut list_title = None;
+    let mut entries = vec![];
+    let mut current: Option<Entry> = None;
+
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+    let mut stack: Vec<String> = vec![];
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                stack.push(name_of(e));
+                if stack.join("/") == entry_path {
+                    current = Some(Entry::default());
+                }
+                if let (Some(entry), Some(url)) = (current.as_mut(), ref_href(e)) {
+                    entry.urls.push(url);
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                if let (Some(entry), Some(url)) = (current.as_mut(), ref_href(e)) {
+                    entry.urls.push(url);
+                }
+            }
+            Ok(Event::End(_)) => {
+                if stack.join("/") == entry_path {
+                    entries.extend(current.take());
+                }
+                stack.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let text = match e.unescape() {
+                    Ok(text) => text.to_string(),
+                    Err(_) => continue,
+                };
+                let path = stack.join("/");
+                if path == list_title_path {
+                    list_title = non_empty(&text);
+                } else if let Some(entry) = current.as_mut() {
+                    let field = path.strip_prefix(entry_path).and_then(|field| field.strip_prefix('/'));
+                    match field {
+                        Some("title") => entry.metadata.title = non_empty(&text),
+                        Some("creator") | Some("author") => entry.metadata.creator = non_empty(&text),
+                        Some("image") => entry.metadata.image = non_empty(&text),
+                        // XSPF duration is in milliseconds
+                        Some("duration") => entry.metadata.duration = duration_seconds(&text).map(|ms| ms / 1000),
+                        Some("location") | Some("identifier") => entry.urls.push(text.trim().to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    (list_title, entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(title: Option<&str>, duration: Option<u64>, creator: Option<&str>, image: Option<&str>) -> PlaylistMetadata {
+        PlaylistMetadata {
+            title: title.map(String::from),
+            duration,
+            creator: creator.map(String::from),
+            image: image.map(String::from),
+        }
+    }
+
+    #[test]
+    fn decode_playlists() {
+        let cases = vec![
+            (
+                "m3u with list title",
+                "#EXTM3U\n#PLAYLIST:The List\n#EXTINF:-1 tvg-logo=\"x\",Radio M3U\r\nlive.mp3\n#EXTINF:120,Other, Show\nshow.mp3\nplain.mp3\n",
+                vec![
+                    ("live.mp3", metadata(Some("Radio M3U"), None, None, None)),
+                    ("show.mp3", metadata(Some("Other, Show"), Some(120), None, None)),
+                    ("plain.mp3", metadata(Some("The List"), None, None, None)),
+                ],
+            ),
+            (
+                "m3u attribute with comma",
+                "#EXTM3U\n#EXTINF:-1 tvg-name=\"a,b\" group-title=\"News\",Radio\nlive.mp3\n",
+                vec![("live.mp3", metadata(Some("Radio"), None, None, None))],
+            ),
+            (
+                "text/plain m3u without header",
+                "#EXTINF:30,Radio\nhttp://example.com/live.mp3\nhttp://example.com/other.mp3\n",
+                vec![("http://example.com/live.mp3", metadata(Some("Radio"), Some(30), None, None))],
+            ),
+            (
+                "pls without NumberOfEntries",
+                "[playlist]\nFile2=two.mp3\nTitle1=Radio PLS\nFile1=one.mp3\nLength1=-1\nLength2=60\n",
+                vec![
+                    ("one.mp3", metadata(Some("Radio PLS"), None, None, None)),
+                    ("two.mp3", metadata(None, Some(60), None, None)),
+                ],
+            ),
+            (
+                "xspf",
+                "<?xml version=\"1.0\"?><playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\"><title>List</title><trackList>\
+                 <track><title>Radio &amp; XSPF</title><creator>Me</creator><image>http://img/x.png</image><duration>5000</duration><location>/live.mp3</location></track>\
+                 <track><location>/other.mp3</location></track></trackList></playlist>",
+                vec![
+                    ("/live.mp3", metadata(Some("Radio & XSPF"), Some(5), Some("Me"), Some("http://img/x.png"))),
+                    ("/other.mp3", metadata(Some("List"), None, None, None)),
+                ],
+            ),
+            (
+                "xspf with identifier only",
+                "<playlist><trackList><track><identifier>http://example.com/live.mp3</identifier><title>Radio</title></track></trackList></playlist>",
+                vec![("http://example.com/live.mp3", metadata(Some("Radio"), None, None, None))],
+            ),
+            (
+                "asx",
+                "<ASX version=\"3.0\"><TITLE>Radio ASX</TITLE><ENTRY><AUTHOR>Someone</AUTHOR><REF HREF=\"/live.mp3\"/></ENTRY></ASX>",
+                vec![("/live.mp3", metadata(Some("Radio ASX"), None, Some("Someone"), None))],
+            ),
+            ("without metadata", "live.mp3\nother.mp3\n", vec![]),
+        ];
+        for (name, content, expected) in cases {
+            let expected: HashMap<String, PlaylistMetadata> = expected.into_iter().map(|(url, metadata)| (url.to_string(), metadata)).collect();
+            assert_eq!(decode(content), expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// A single followed http redirect
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Redirect {
+    /// Url which answered with the redirect
+    pub url: String,
+    /// Url the redirect points to
+    pub location: String,
+    /// Http status code of the redirect response (3xx)
+    pub status: u32,
+    /// Redirect is permanent (301, 308), stored urls may be replaced by the location
+    pub permanent: bool,
+}
+
+impl Redirect {
+    /// Permanent redirect status codes are 301 (Moved Permanently) and 308 (Permanent Redirect),
+    /// all others (302, 303, 307) are temporary.
+    pub fn is_permanent_status(status: u32) -> bool {
+        status == 301 || status == 308
+    }
+}
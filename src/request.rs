@@ -1,8 +1,11 @@
+use crate::cancellation::{CancellationToken, Registration};
+use crate::charset;
+
 use native_tls::TlsConnector;
 
 use std::fmt;
 
-use std::io::{Read, Write};
+use std::io::{Chain, Cursor, Read, Write};
 use std::net::SocketAddr;
 use std::net::TcpStream;
 
@@ -37,6 +40,7 @@ impl Error for RequestError {
     }
 }
 
+#[allow(dead_code)]
 pub struct HttpHeaders {
     pub code: u32,
     pub message: String,
@@ -44,12 +48,32 @@ pub struct HttpHeaders {
     pub headers: HashMap<String, String>,
 }
 
+impl HttpHeaders {
+    /// Response had a "ICY 200 OK" status line instead of a HTTP status line
+    pub fn is_icy(&self) -> bool {
+        self.version.is_empty()
+    }
+}
+
 pub struct Request {
     pub info: HttpHeaders,
-    readable: Box<dyn Read>,
+    readable: Box<dyn Read + Send + Sync>,
     content_read_done: bool,
     content_vec: Vec<u8>,
     ssl_error: bool,
+    _registration: Option<Registration>,
+}
+
+/// Remaining content of a request, see `Request::into_reader`
+pub struct ContentReader {
+    reader: Chain<Cursor<Vec<u8>>, Box<dyn Read + Send + Sync>>,
+    _registration: Option<Registration>,
+}
+
+impl Read for ContentReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
 }
 
 use std::net::ToSocketAddrs;
@@ -63,11 +87,13 @@ fn connect(addrs: Box<IntoIter<SocketAddr>>, timeout: u32) -> BoxResult<TcpStrea
             return Ok(stream);
         }
     }
-    return Err(Box::new(RequestError::new("connection was not possible")));
+    Err(Box::new(RequestError::new("connection was not possible")))
 }
 
 impl Request {
-    pub fn new(url_str: &str, agent: &str, timeout: u32) -> BoxResult<Request> {
+    /// Connect and read the response header. If a cancellation token is given,
+    /// cancelling it closes the connection.
+    pub fn new(url_str: &str, agent: &str, timeout: u32, cancellation: Option<&CancellationToken>) -> BoxResult<Request> {
         let url = Url::parse(url_str)?;
 
         let host = url
@@ -81,6 +107,7 @@ impl Request {
         let addrs_iter = connect_str.to_socket_addrs()?;
         let mut stream: TcpStream = connect(Box::new(addrs_iter), timeout)?;
         stream.set_read_timeout(Some(Duration::from_secs(timeout as u64)))?;
+        let mut registration = cancellation.and_then(|token| token.register(&stream));
 
         if url.scheme() == "https" {
             let mut connector = TlsConnector::builder().build()?;
@@ -93,6 +120,7 @@ impl Request {
                 let addrs_iter = connect_str.to_socket_addrs()?;
                 let stream: TcpStream = connect(Box::new(addrs_iter), timeout)?;
                 stream.set_read_timeout(Some(Duration::from_secs(timeout as u64)))?;
+                registration = cancellation.and_then(|token| token.register(&stream));
                 connector = TlsConnector::builder()
                     .danger_accept_invalid_certs(true)
                     .danger_accept_invalid_hostnames(true)
@@ -119,6 +147,7 @@ impl Request {
                 content_read_done: false,
                 content_vec: vec![],
                 ssl_error,
+                _registration: registration,
             })
         } else if url.scheme() == "http" {
             let mut host_str = String::from(host);
@@ -139,6 +168,7 @@ impl Request {
                 content_read_done: false,
                 content_vec: vec![],
                 ssl_error: false,
+                _registration: registration,
             })
         } else {
             Err(Box::new(RequestError::new("unknown scheme")))
@@ -159,7 +189,6 @@ impl Request {
                 break;
             } else {
                 self.content_vec.extend(buffer[0..bytes].iter());
-                buffer.clear();
             }
         }
         Ok(())
@@ -186,17 +215,25 @@ impl Request {
         Ok(content_length)
     }
 
-    pub fn text<'a>(&'a self) -> String {
-        let out = String::from_utf8_lossy(&self.content_vec);
-        let content = out.to_string();
-        return content.clone();
+    /// Content as text, decoded with the charset from the content type if known
+    pub fn text(&self, charset: Option<&str>) -> String {
+        charset::decode(&self.content_vec, charset)
     }
 
-    //pub fn bytes<'a>(&'a self) -> &'a [u8] {
-    //    self.content_vec.as_slice()
-    //}
+    /// Convert into a reader for the remaining content, including already read bytes.
+    /// The connection stays registered for cancellation while the reader is alive.
+    pub fn into_reader(self) -> ContentReader {
+        ContentReader {
+            reader: Cursor::new(self.content_vec).chain(self.readable),
+            _registration: self._registration,
+        }
+    }
 
-    fn read_stream_until(stream: &mut dyn Read, condition: &'static [u8]) -> BoxResult<String> {
+    pub fn bytes(&self) -> &[u8] {
+        self.content_vec.as_slice()
+    }
+
+    fn read_stream_until(stream: &mut dyn Read, condition: &'static [u8]) -> BoxResult<Vec<u8>> {
         let mut buffer = vec![0; 1];
         let mut bytes = Vec::new();
         loop {
@@ -223,8 +260,7 @@ impl Request {
                 break;
             }
         }
-        let out = String::from_utf8_lossy(&bytes);
-        Ok(out.to_string())
+        Ok(bytes)
     }
 
     fn send_request(agent: &str, stream: &mut dyn Write, host: &str, path: &str) -> BoxResult<()> {
@@ -232,7 +268,7 @@ impl Request {
             "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: */*\r\nUser-Agent: {}\r\nConnection: close\r\n\r\n",
             path, host, agent
         );
-        stream.write(request_str.as_bytes())?;
+        stream.write_all(request_str.as_bytes())?;
         stream.flush()?;
         Ok(())
     }
@@ -256,33 +292,32 @@ impl Request {
                 headers: HashMap::new(),
             })
         } else {
-            return Err(Box::new(RequestError::new("HTTP header missing")));
+            Err(Box::new(RequestError::new("HTTP header missing")))
         }
     }
 
     fn read_request(stream: &mut dyn Read) -> BoxResult<HttpHeaders> {
         let out = Request::read_stream_until(stream, b"\r\n")?;
-        let mut httpinfo = Request::decode_first_line(&out)?;
+        let mut httpinfo = Request::decode_first_line(&String::from_utf8_lossy(&out))?;
 
+        // header values are not always UTF-8, e.g. icy-name in Latin-1
         let out = Request::read_stream_until(stream, b"\r\n\r\n")?;
-        let lines = out.lines();
+        let lines = out.split(|byte| *byte == b'\n');
 
         for line in lines {
-            match line.find(':') {
-                Some(index) => {
-                    let (key, value) = line.split_at(index);
-                    let key_trimmed = String::from(key).to_lowercase();
-                    let value_trimmed = String::from(value[1..].trim());
-                    httpinfo
-                        .headers
-                        .entry(key_trimmed)
-                        .and_modify(|s| {
-                            s.push_str(",");
-                            s.push_str(&value_trimmed);
-                        })
-                        .or_insert(value_trimmed);
-                }
-                _ => {}
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if let Some(index) = line.iter().position(|byte| *byte == b':') {
+                let (key, value) = line.split_at(index);
+                let key_trimmed = String::from_utf8_lossy(key).to_lowercase();
+                let value_trimmed = charset::decode_header(&value[1..], &out).trim().to_string();
+                httpinfo
+                    .headers
+                    .entry(key_trimmed)
+                    .and_modify(|s| {
+                        s.push(',');
+                        s.push_str(&value_trimmed);
+                    })
+                    .or_insert(value_trimmed);
             }
         }
         Ok(httpinfo)
@@ -0,0 +1,87 @@
+use crate::StreamCheckError;
+use crate::StreamCheckResult;
+use crate::StreamInfo;
+use crate::UrlType;
+
+/// A node of a result tree together with the urls which lead to it
+#[derive(Debug, Clone)]
+pub struct ResultNode<'a> {
+    pub result: &'a StreamCheckResult,
+    /// Urls from the root of the tree to this node, including the url of this node
+    pub path: Vec<&'a str>,
+}
+
+/// A working stream in a result tree
+#[derive(Debug, Clone)]
+pub struct FoundStream<'a> {
+    /// Final url which delivers the stream, after all redirects and playlists
+    pub url: &'a str,
+    pub info: &'a StreamInfo,
+    /// Urls from the root of the tree to the stream, including the final url
+    pub path: Vec<&'a str>,
+}
+
+/// An error in a result tree
+#[derive(Debug, Clone)]
+pub struct FoundError<'a> {
+    pub url: &'a str,
+    pub error: &'a StreamCheckError,
+    /// Urls from the root of the tree to the failed url, including the failed url
+    pub path: Vec<&'a str>,
+}
+
+/// Depth first iterator over all nodes of a result tree, created by [`StreamCheckResult::nodes`]
+pub struct Nodes<'a> {
+    stack: Vec<(&'a StreamCheckResult, Vec<&'a str>)>,
+}
+
+impl<'a> Nodes<'a> {
+    pub(crate) fn new(root: &'a StreamCheckResult) -> Self {
+        Nodes {
+            stack: vec![(root, vec![])],
+        }
+    }
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = ResultNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (result, mut path) = self.stack.pop()?;
+        path.push(result.url());
+        match &result.info {
+            Ok(UrlType::Redirect(item)) => self.stack.push((item, path.clone())),
+            Ok(UrlType::PlayList(list)) => {
+                for item in list.iter().rev() {
+                    self.stack.push((item, path.clone()));
+                }
+            }
+            _ => {}
+        }
+        Some(ResultNode { result, path })
+    }
+}
+
+impl<'a> ResultNode<'a> {
+    pub(crate) fn into_stream(self) -> Option<FoundStream<'a>> {
+        match &self.result.info {
+            Ok(UrlType::Stream(info)) => Some(FoundStream {
+                url: self.result.url(),
+                info,
+                path: self.path,
+            }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn into_error(self) -> Option<FoundError<'a>> {
+        match &self.result.info {
+            Err(error) => Some(FoundError {
+                url: self.result.url(),
+                error,
+                path: self.path,
+            }),
+            _ => None,
+        }
+    }
+}
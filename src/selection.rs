@@ -0,0 +1,155 @@
+use crate::AudioCodec;
+use crate::FoundStream;
+use crate::StreamCheckResult;
+
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+/// Preferences for choosing one of several working streams with [`select_best`]
+///
+/// # Example
+/// ```rust
+/// use av_stream_info_rust::{AacProfile, AudioCodec, StreamPreferences};
+///
+/// let preferences = StreamPreferences {
+///     codecs: vec![AudioCodec::Aac(AacProfile::He), AudioCodec::Mp3],
+///     min_bitrate: Some(64),
+///     max_bitrate: Some(192),
+///     ..StreamPreferences::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamPreferences {
+    /// Preferred audio codecs, earlier entries are preferred over later ones,
+    /// codecs not in the list come last
+    pub codecs: Vec<AudioCodec>,
+    /// Streams with a known bitrate below are only chosen if there is nothing else
+    pub min_bitrate: Option<u32>,
+    /// Streams with a known bitrate above are only chosen if there is nothing else
+    pub max_bitrate: Option<u32>,
+    /// Prefer https urls over http
+    pub prefer_https: bool,
+    /// Prefer streams without certificate problems
+    pub prefer_no_ssl_error: bool,
+    /// Prefer plain streams over HLS
+    pub prefer_non_hls: bool,
+}
+
+impl Default for StreamPreferences {
+    fn default() -> Self {
+        StreamPreferences {
+            codecs: vec![],
+            min_bitrate: None,
+            max_bitrate: None,
+            prefer_https: true,
+            prefer_no_ssl_error: true,
+            prefer_non_hls: false,
+        }
+    }
+}
+
+/// Why a stream was chosen, the first preference in which it was better than the runner-up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SelectionReason {
+    /// There was only one working stream
+    OnlyCandidate(),
+    BitrateInRange(),
+    NoSslError(),
+    PreferredCodec(AudioCodec),
+    Https(),
+    NotHls(),
+    HigherBitrate(u32),
+    /// All preferences were equal, the stream was found first
+    FirstFound(),
+}
+
+/// Stream chosen by [`select_best`]
+#[derive(Debug, Clone)]
+pub struct Selection<'a> {
+    pub stream: FoundStream<'a>,
+    pub reason: SelectionReason,
+}
+
+impl<'a> Selection<'a> {
+    /// Final url of the chosen stream
+    pub fn url(&self) -> &'a str {
+        self.stream.url
+    }
+}
+
+/// Ranking of a stream, lower is better
+type Rank = (bool, bool, usize, bool, bool, Reverse<u32>);
+
+fn rank(stream: &FoundStream, preferences: &StreamPreferences) -> Rank {
+    let info = stream.info;
+    let out_of_range = match info.Bitrate {
+        Some(bitrate) => {
+            preferences.min_bitrate.map(|min| bitrate < min).unwrap_or(false)
+                || preferences.max_bitrate.map(|max| bitrate > max).unwrap_or(false)
+        }
+        None => false,
+    };
+    let codec_rank = preferences
+        .codecs
+        .iter()
+        .position(|codec| *codec == info.CodecAudio)
+        .unwrap_or(preferences.codecs.len());
+    (
+        out_of_range,
+        preferences.prefer_no_ssl_error && info.SslError,
+        codec_rank,
+        preferences.prefer_https && !stream.url.starts_with("https:"),
+        preferences.prefer_non_hls && info.Hls,
+        Reverse(info.Bitrate.unwrap_or(0)),
+    )
+}
+
+fn reason(best: &Rank, runner_up: &Rank, best_stream: &FoundStream) -> SelectionReason {
+    if best.0 != runner_up.0 {
+        SelectionReason::BitrateInRange()
+    } else if best.1 != runner_up.1 {
+        SelectionReason::NoSslError()
+    } else if best.2 != runner_up.2 {
+        SelectionReason::PreferredCodec(best_stream.info.CodecAudio)
+    } else if best.3 != runner_up.3 {
+        SelectionReason::Https()
+    } else if best.4 != runner_up.4 {
+        SelectionReason::NotHls()
+    } else if best.5 != runner_up.5 {
+        SelectionReason::HigherBitrate(best.5 .0)
+    } else {
+        SelectionReason::FirstFound()
+    }
+}
+
+/// Choose the best of all working streams in a result tree.
+/// Preferences are applied in this order: bitrate range, no ssl errors, codec,
+/// https, non HLS, higher bitrate. If all are equal, the stream found first wins.
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{select_best, StreamPreferences};
+///
+/// let item = av_stream_info_rust::check_tree("https://example.com/test.pls", 10, 3, 3, false);
+/// if let Some(selection) = select_best(&item, &StreamPreferences::default()) {
+///     println!("{} {:?}", selection.url(), selection.reason);
+/// }
+/// ```
+pub fn select_best<'a>(result: &'a StreamCheckResult, preferences: &StreamPreferences) -> Option<Selection<'a>> {
+    let mut ranked: Vec<(Rank, FoundStream<'a>)> = result
+        .ok_streams()
+        .into_iter()
+        .map(|stream| (rank(&stream, preferences), stream))
+        .collect();
+    // stable, keeps the order of the tree for equal ranks
+    ranked.sort_by_key(|(rank, _)| *rank);
+
+    let mut ranked = ranked.into_iter();
+    let (best_rank, best) = ranked.next()?;
+    let reason = match ranked.next() {
+        Some((runner_up_rank, _)) => reason(&best_rank, &runner_up_rank, &best),
+        None => SelectionReason::OnlyCandidate(),
+    };
+    Some(Selection { stream: best, reason })
+}
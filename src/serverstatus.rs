@@ -0,0 +1,262 @@
+use crate::cancellation::CancellationToken;
+use crate::request::Request;
+use crate::HeaderDialect;
+use crate::StreamInfo;
+
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+/// Shoutcast 1 only answers with the status page if the user agent looks like a browser
+const AGENT: &str = "Mozilla/5.0 (compatible; StreamCheckBot/0.1.0)";
+
+/// Kind of streaming server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerType {
+    Icecast,
+    Shoutcast,
+}
+
+/// Information from the status page of an Icecast or Shoutcast server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerStatus {
+    pub server_type: ServerType,
+    /// Url of the status page
+    pub status_url: String,
+    pub current_listeners: Option<u32>,
+    pub peak_listeners: Option<u32>,
+    pub max_listeners: Option<u32>,
+    /// Urls of other mount points on the same server
+    pub mounts: Vec<String>,
+    /// Start of the stream as reported by Icecast (ISO 8601)
+    pub stream_start: Option<String>,
+    /// Seconds since start of the stream as reported by Shoutcast
+    pub stream_uptime: Option<u64>,
+}
+
+/// Posible errors for fetching the server status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerStatusError {
+    UnknownServer(),
+    UrlParseError(),
+    ConnectionFailed(),
+    IllegalStatusCode(u32),
+    DecodeError(),
+    MountNotFound(),
+    NoWorkingStream(),
+}
+
+impl fmt::Display for ServerStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServerStatusError::UnknownServer() => write!(f, "UnknownServer()"),
+            ServerStatusError::UrlParseError() => write!(f, "UrlParseError()"),
+            ServerStatusError::ConnectionFailed() => write!(f, "ConnectionFailed()"),
+            ServerStatusError::IllegalStatusCode(code) => write!(f, "IllegalStatusCode({})", code),
+            ServerStatusError::DecodeError() => write!(f, "DecodeError()"),
+            ServerStatusError::MountNotFound() => write!(f, "MountNotFound()"),
+            ServerStatusError::NoWorkingStream() => write!(f, "NoWorkingStream()"),
+        }
+    }
+}
+
+impl Error for ServerStatusError {}
+
+/// Detect server type from the "Server" response header and the header dialect
+pub fn detect_server_type(stream: &StreamInfo) -> Option<ServerType> {
+    let server = stream.Server.clone().unwrap_or_default().to_lowercase();
+    if server.contains("icecast") {
+        Some(ServerType::Icecast)
+    } else if server.contains("shoutcast") || stream.Dialect == HeaderDialect::ShoutcastV1 {
+        Some(ServerType::Shoutcast)
+    } else {
+        None
+    }
+}
+
+/// Fetch the status page of the server which hosts the stream url
+///
+/// # Example
+/// ```no_run
+/// use av_stream_info_rust::{fetch_server_status, ServerType};
+///
+/// let status = fetch_server_status("http://example.com:8000/live.mp3", ServerType::Icecast, 10);
+/// println!("{:#?}", status);
+/// ```
+pub fn fetch_server_status(stream_url: &str, server_type: ServerType, timeout: u32) -> Result<ServerStatus, ServerStatusError> {
+    fetch(stream_url, server_type, timeout, None)
+}
+
+/// Fetch the status page, abort if the cancellation token is cancelled
+pub(crate) fn fetch(stream_url: &str, server_type: ServerType, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<ServerStatus, ServerStatusError> {
+    let stream_url = Url::parse(stream_url).or(Err(ServerStatusError::UrlParseError()))?;
+    match server_type {
+        ServerType::Icecast => fetch_icecast(&stream_url, timeout, cancellation),
+        ServerType::Shoutcast => fetch_shoutcast_v2(&stream_url, timeout, cancellation)
+            .or_else(|err| {
+                debug!("shoutcast 2 statistics failed ({}), try stats of default stream", err);
+                fetch_shoutcast_v2_stats(&stream_url, timeout, cancellation)
+            })
+            .or_else(|err| {
+                debug!("shoutcast 2 status failed ({}), try shoutcast 1", err);
+                fetch_shoutcast_v1(&stream_url, timeout, cancellation)
+            }),
+    }
+}
+
+fn download(url: &Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<String, ServerStatusError> {
+    trace!("download server status '{}'", url);
+    let mut request = Request::new(url.as_str(), AGENT, timeout, cancellation).or(Err(ServerStatusError::ConnectionFailed()))?;
+    if request.info.code != 200 {
+        return Err(ServerStatusError::IllegalStatusCode(request.info.code));
+    }
+    request.read_up_to(1_000_000).or(Err(ServerStatusError::ConnectionFailed()))?;
+    Ok(request.text(None))
+}
+
+/// Make an absolute url on the same server from the path of a mount point
+fn mount_url(stream_url: &Url, mount: &str) -> Option<String> {
+    let path = match Url::parse(mount) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => mount.to_string(),
+    };
+    stream_url.join(&path).ok().map(|url| url.to_string())
+}
+
+fn json_u32(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(number) => number.as_u64().map(|n| n as u32),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn fetch_icecast(stream_url: &Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<ServerStatus, ServerStatusError> {
+    let status_url = stream_url.join("/status-json.xsl").or(Err(ServerStatusError::UrlParseError()))?;
+    let content = download(&status_url, timeout, cancellation)?;
+    let json: Value = serde_json::from_str(&content).or(Err(ServerStatusError::DecodeError()))?;
+    // "source" is an object if there is only one mount point
+    let sources = match &json["icestats"]["source"] {
+        Value::Array(list) => list.clone(),
+        Value::Object(_) => vec![json["icestats"]["source"].clone()],
+        _ => vec![],
+    };
+
+    let mut current: Option<&Value> = None;
+    let mut mounts = vec![];
+    for source in sources.iter() {
+        let listen_url = source["listenurl"].as_str().and_then(|mount| mount_url(stream_url, mount));
+        if let Some(listen_url) = listen_url {
+            if Url::parse(&listen_url).map(|u| u.path() == stream_url.path()).unwrap_or(false) {
+                current = Some(source);
+            } else {
+                mounts.push(listen_url);
+            }
+        }
+    }
+    let current = current.ok_or(ServerStatusError::MountNotFound())?;
+
+    Ok(ServerStatus {
+        server_type: ServerType::Icecast,
+        status_url: status_url.to_string(),
+        current_listeners: json_u32(&current["listeners"]),
+        peak_listeners: json_u32(&current["listener_peak"]),
+        max_listeners: json_u32(&current["max_listeners"]),
+        mounts,
+        stream_start: current["stream_start_iso8601"]
+            .as_str()
+            .or(current["stream_start"].as_str())
+            .map(String::from),
+        stream_uptime: None,
+    })
+}
+
+fn fetch_shoutcast_v2(stream_url: &Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<ServerStatus, ServerStatusError> {
+    let status_url = stream_url.join("/statistics?json=1").or(Err(ServerStatusError::UrlParseError()))?;
+    let content = download(&status_url, timeout, cancellation)?;
+    let json: Value = serde_json::from_str(&content).or(Err(ServerStatusError::DecodeError()))?;
+    let streams = json["streams"].as_array().ok_or(ServerStatusError::DecodeError())?;
+
+    // the default stream is reachable with the root path and its own path
+    let path = stream_url.path();
+    let is_current = |stream: &Value| {
+        let stream_path = stream["streampath"].as_str().unwrap_or("");
+        stream_path == path || ((path == "/" || path == "/;") && json_u32(&stream["id"]) == Some(1))
+    };
+    let current = streams
+        .iter()
+        .find(|stream| is_current(stream))
+        .ok_or(ServerStatusError::MountNotFound())?;
+    let mounts = streams
+        .iter()
+        .filter(|stream| !is_current(stream))
+        .filter_map(|stream| stream["streampath"].as_str())
+        .filter_map(|mount| mount_url(stream_url, mount))
+        .collect();
+
+    Ok(ServerStatus {
+        server_type: ServerType::Shoutcast,
+        status_url: status_url.to_string(),
+        current_listeners: json_u32(&current["currentlisteners"]),
+        peak_listeners: json_u32(&current["peaklisteners"]),
+        max_listeners: json_u32(&current["maxlisteners"]),
+        mounts,
+        stream_start: None,
+        stream_uptime: json_u32(&current["streamuptime"]).map(|n| n as u64),
+    })
+}
+
+/// Stats of the default stream only, for servers without the statistics page
+fn fetch_shoutcast_v2_stats(stream_url: &Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<ServerStatus, ServerStatusError> {
+    let status_url = stream_url.join("/stats?json=1").or(Err(ServerStatusError::UrlParseError()))?;
+    let content = download(&status_url, timeout, cancellation)?;
+    let json: Value = serde_json::from_str(&content).or(Err(ServerStatusError::DecodeError()))?;
+    if !json.is_object() {
+        return Err(ServerStatusError::DecodeError());
+    }
+
+    Ok(ServerStatus {
+        server_type: ServerType::Shoutcast,
+        status_url: status_url.to_string(),
+        current_listeners: json_u32(&json["currentlisteners"]),
+        peak_listeners: json_u32(&json["peaklisteners"]),
+        max_listeners: json_u32(&json["maxlisteners"]),
+        mounts: vec![],
+        stream_start: None,
+        stream_uptime: json_u32(&json["streamuptime"]).map(|n| n as u64),
+    })
+}
+
+fn fetch_shoutcast_v1(stream_url: &Url, timeout: u32, cancellation: Option<&CancellationToken>) -> Result<ServerStatus, ServerStatusError> {
+    let status_url = stream_url.join("/7.html").or(Err(ServerStatusError::UrlParseError()))?;
+    let content = download(&status_url, timeout, cancellation)?;
+
+    // <html><body>CURRENTLISTENERS,STREAMSTATUS,PEAKLISTENERS,MAXLISTENERS,UNIQUELISTENERS,BITRATE,SONGTITLE</body></html>
+    let start = find_ignore_case(&content, "<body>").ok_or(ServerStatusError::DecodeError())? + "<body>".len();
+    let end = find_ignore_case(&content[start..], "</body>").map(|end| start + end).unwrap_or(content.len());
+    let values: Vec<&str> = content[start..end].splitn(7, ',').collect();
+    if values.len() < 4 {
+        return Err(ServerStatusError::DecodeError());
+    }
+
+    Ok(ServerStatus {
+        server_type: ServerType::Shoutcast,
+        status_url: status_url.to_string(),
+        current_listeners: values[0].trim().parse().ok(),
+        peak_listeners: values[2].trim().parse().ok(),
+        max_listeners: values[3].trim().parse().ok(),
+        mounts: vec![],
+        stream_start: None,
+        stream_uptime: None,
+    })
+}
+
+/// Byte position of an ASCII needle in the text, ignoring ASCII case
+fn find_ignore_case(text: &str, needle: &str) -> Option<usize> {
+    text.as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
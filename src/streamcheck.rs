@@ -1,320 +1,538 @@
 #![allow(non_snake_case)]
+use crate::checkcontext::CheckContext;
 use crate::request::Request;
+use crate::serverstatus;
+use crate::headerconflict;
+use crate::codec;
+use crate::htmllinks;
+use crate::isocodes;
+use crate::http_config;
+use crate::loudness;
+use crate::playlistformat;
+use crate::playlistmetadata;
+use crate::streamdeepscan;
 
+use crate::AudioCodec;
+use crate::AudioParameters;
+use crate::CheckEvent;
+use crate::DecodeError;
+use crate::HeaderDialect;
+use crate::ServerStatusError;
+use crate::Container;
 use crate::StreamCheckResult;
 use crate::StreamCheckError;
 use crate::LatLong;
+use crate::PlaylistFormat;
+use crate::PlaylistMetadata;
 use crate::StreamInfo;
 use crate::UrlType;
+use crate::VideoCodec;
 
-use playlist_decoder;
 use url::Url;
 use hls_m3u8::MasterPlaylist;
 use core::convert::TryFrom;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use serde::{Deserialize, Serialize};
-//use crate::streamdeepscan;
 
 use log::{debug};
 
 fn type_is_m3u(content_type: &str) -> bool {
-    return content_type == "application/mpegurl" || content_type == "application/x-mpegurl"
+    content_type == "application/mpegurl" || content_type == "application/x-mpegurl"
         || content_type == "audio/mpegurl" || content_type == "audio/x-mpegurl"
         || content_type == "application/vnd.apple.mpegurl"
-        || content_type == "application/vnd.apple.mpegurl.audio";
+        || content_type == "application/vnd.apple.mpegurl.audio"
 }
 
 fn type_is_pls(content_type: &str) -> bool {
-    return content_type == "audio/x-scpls" || content_type == "application/x-scpls"
-        || content_type == "application/pls+xml";
+    content_type == "audio/x-scpls" || content_type == "application/x-scpls"
+        || content_type == "application/pls+xml"
 }
 
 fn type_is_asx(content_type: &str) -> bool {
-    return content_type == "video/x-ms-asx" || content_type == "video/x-ms-asf";
+    content_type == "video/x-ms-asx" || content_type == "video/x-ms-asf"
 }
 
 fn type_is_xspf(content_type: &str) -> bool {
-    return content_type == "application/xspf+xml";
+    content_type == "application/xspf+xml"
 }
 
-fn type_is_playlist(content_type: &str) -> bool {
-    let search = content_type.find(";");
-    let mut content_type = content_type;
-    match search {
-        Some(index) => {
-            content_type = &content_type[0..index];
-        }
-        None => {}
-    }
-    return type_is_m3u(content_type) || type_is_pls(content_type) || type_is_asx(content_type)
-        || type_is_xspf(content_type);
+fn type_is_hls(content_type: &str) -> bool {
+    content_type == "application/vnd.apple.mpegurl"
+        || content_type == "application/vnd.apple.mpegurl.audio"
 }
 
-fn type_is_stream_with_oktet(content_type: &str) -> Option<&str> {
-    match content_type {
-        "audio/mpeg" => Some("MP3"),
-        "audio/x-mpeg" => Some("MP3"),
-        "audio/mp3" => Some("MP3"),
-        "audio/aac" => Some("AAC"),
-        "audio/x-aac" => Some("AAC"),
-        "audio/aacp" => Some("AAC+"),
-        "audio/ogg" => Some("OGG"),
-        "application/ogg" => Some("OGG"),
-        "video/ogg" => Some("OGG"),
-        "audio/flac" => Some("FLAC"),
-        "application/flv" => Some("FLV"),
-        "application/octet-stream" => Some("UNKNOWN"),
-        _ => None,
-    }
+fn type_is_mpd(content_type: &str) -> bool {
+    content_type == "application/dash+xml"
 }
 
-fn type_is_stream_without_oktet(content_type: &str) -> Option<&str> {
-    match content_type {
-        "audio/mpeg" => Some("MP3"),
-        "audio/x-mpeg" => Some("MP3"),
-        "audio/mp3" => Some("MP3"),
-        "audio/aac" => Some("AAC"),
-        "audio/x-aac" => Some("AAC"),
-        "audio/aacp" => Some("AAC+"),
-        "audio/ogg" => Some("OGG"),
-        "application/ogg" => Some("OGG"),
-        "video/ogg" => Some("OGG"),
-        "audio/flac" => Some("FLAC"),
-        "application/flv" => Some("FLV"),
-        _ => None,
+fn playlist_format_of_type(content_type: &str) -> Option<PlaylistFormat> {
+    let content_type = match content_type.find(';') {
+        Some(index) => &content_type[0..index],
+        None => content_type,
+    };
+    if type_is_hls(content_type) {
+        Some(PlaylistFormat::Hls)
+    } else if type_is_m3u(content_type) {
+        Some(PlaylistFormat::M3u)
+    } else if type_is_pls(content_type) {
+        Some(PlaylistFormat::Pls)
+    } else if type_is_asx(content_type) {
+        Some(PlaylistFormat::Asx)
+    } else if type_is_xspf(content_type) {
+        Some(PlaylistFormat::Xspf)
+    } else if type_is_mpd(content_type) {
+        Some(PlaylistFormat::Mpd)
+    } else {
+        None
     }
 }
 
 fn type_is_definitelly_not_usefull(content_type: &str) -> bool {
-    match content_type {
-        "text/html" => true,
-        _ => false,
-    }
+    matches!(content_type, "text/html")
 }
 
+/// Bytes read from responses without a stream content type to detect playlists by their content
+const SNIFF_SIZE: usize = 1024;
+/// Html pages are only searched for links up to this size
+const HTML_MAX_SIZE: usize = 1_000_000;
+
 #[derive(Debug,Serialize,Deserialize,Clone)]
 enum LinkType {
-    Stream(String),
-    Playlist(String),
+    Stream(Container, AudioCodec),
+    Playlist(String, PlaylistFormat),
+    Html(String),
     Other
 }
 
+/// Absolute url of a playlist entry
+struct PlaylistEntry {
+    url: String,
+    metadata: Option<PlaylistMetadata>,
+}
+
+/// Split content type header into the lower case mime type and the charset parameter,
+/// the charset is empty if not declared
 fn decode_content_type(content_type_header: &str) -> (String,String){
     let content_type_header = content_type_header.to_lowercase();
-    let mut content_type_header_iter = content_type_header.split(";");
+    let mut content_type_header_iter = content_type_header.split(';');
     let content_type_lower_real = content_type_header_iter.next().unwrap_or("text/html").trim();
-    let content_type_lower_charset = content_type_header_iter.next().unwrap_or("charset=utf-8").trim();
+    let content_type_lower_charset = content_type_header_iter
+        .filter_map(|parameter| parameter.trim().strip_prefix("charset="))
+        .map(|charset| charset.trim_matches('"'))
+        .next()
+        .unwrap_or("");
 
     (content_type_lower_real.to_string(), content_type_lower_charset.to_string())
 }
 
-fn get_type(content_type_header: &str, charset: &str, content_length: Option<usize>) -> LinkType {
+fn get_type(content_type_header: &str, charset: &str, sniffed: Option<PlaylistFormat>, html_links: bool) -> LinkType {
+    trace!("charset: {}", charset);
+    // content of the body wins over missing, generic or playlist content types
+    if let Some(format) = sniffed {
+        return LinkType::Playlist(charset.to_string(), format);
+    }
     if type_is_definitelly_not_usefull(content_type_header) {
+        if html_links {
+            return LinkType::Html(charset.to_string());
+        }
         return LinkType::Other;
     }
-    if let Some(stream_type) = type_is_stream_without_oktet(&content_type_header) {
-        return LinkType::Stream(String::from(stream_type));
+    if let Some((container, codec)) = codec::from_mime(content_type_header, false) {
+        return LinkType::Stream(container, codec);
     }
-    if type_is_playlist(&content_type_header) || content_length.is_some() {
-        let charset = if charset.starts_with("charset=") {&charset[8..]} else {""};
-        trace!("charset: {}", charset);
-        LinkType::Playlist(charset.to_string())
-    } else if type_is_stream_with_oktet(&content_type_header).is_some() {
-        LinkType::Stream(String::from(type_is_stream_with_oktet(&content_type_header).unwrap_or("")))
+    if let Some(format) = playlist_format_of_type(content_type_header) {
+        LinkType::Playlist(charset.to_string(), format)
+    } else if let Some((container, codec)) = codec::from_mime(content_type_header, true) {
+        LinkType::Stream(container, codec)
     } else {
         LinkType::Other
     }
 }
 
-fn handle_playlist(mut request: Request, url: &str, early_exit_on_first_ok: bool, timeout: u32, max_depth: u8) -> Vec<StreamCheckResult> {
+fn handle_playlist(mut request: Request, url: &str, charset: &str, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> Vec<StreamCheckResult> {
+    match request.read_content() {
+        Ok(_) => handle_playlist_content(&request.text(Some(charset).filter(|charset| !charset.is_empty())), request.had_ssl_error(), url, ctx, max_depth, visited),
+        Err(_err) => {
+            let error = ctx.interrupted().unwrap_or(StreamCheckError::PlaylistReadFailed());
+            vec![failed(url, error, ctx)]
+        }
+    }
+}
+
+/// Decode playlist content and check its entries, `url` is the base for relative entries
+fn handle_playlist_content(content: &str, ssl_error: bool, url: &str, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> Vec<StreamCheckResult> {
     let mut list: Vec<StreamCheckResult> = vec![];
-    let read_result = request.read_content();
-    match read_result {
-        Ok(_)=>{
-            let ssl_error = request.had_ssl_error();
-            let content = request.text();
-            let is_hls = playlist_decoder::is_content_hls(&content);
-            if is_hls {
-                let playlist = MasterPlaylist::try_from(&content[..]);
-                match playlist{
-                    Ok(playlist)=>{
-                        for i in playlist.variant_streams {
-                            let mut audio = String::from("UNKNOWN");
-                            let mut video: Option<String> = None;
-                            let codecs_obj = i.codecs();
-                            if let Some(codecs_obj) = codecs_obj {
-                                let (a,v) = decode_hls_codecs(&codecs_obj.to_string());
-                                audio = a;
-                                video = v;
-                            }
-                            let stream = StreamInfo {
-                                Server: None,
-                                Public: None,
-                                IceAudioInfo: None,
-                                AudioInfo: None,
-                                Type: String::from(""),
-                                Name: None,
-                                Description: None,
-                                Homepage: None,
-                                Bitrate: Some((i.bandwidth() as u32) / 1000),
-                                Genre: None,
-                                Sampling: None,
-                                CodecAudio: audio,
-                                CodecVideo: video,
-                                Hls: true,
-                                LogoUrl: None,
-                                MainStreamUrl: None,
-                                IcyVersion: 1,
-                                OverrideIndexMetaData: None,
-                                CountryCode: None,
-                                CountrySubdivisonCode: None,
-                                LanguageCodes: vec![],
-                                DoNotIndex: None,
-                                SslError: ssl_error,
-                                GeoLatLong: None,
-                            };
-                            list.push(StreamCheckResult::new(url, Ok(UrlType::Stream(stream))));
-                            break;
-                        }
-                    }
-                    Err(_)=>{
-                        let stream = StreamInfo {
-                            Server: None,
-                            Public: None,
-                            IceAudioInfo: None,
-                            AudioInfo: None,
-                            Type: String::from(""),
-                            Name: None,
-                            Description: None,
-                            Homepage: None,
-                            Bitrate: None,
-                            Genre: None,
-                            Sampling: None,
-                            CodecAudio: String::from("UNKNOWN"),
-                            CodecVideo: None,
-                            Hls: true,
-                            LogoUrl: None,
-                            MainStreamUrl: None,
-                            IcyVersion: 1,
-                            OverrideIndexMetaData: None,
-                            CountryCode: None,
-                            CountrySubdivisonCode: None,
-                            LanguageCodes: vec![],
-                            DoNotIndex: None,
-                            SslError: ssl_error,
-                            GeoLatLong: None,
-                        };
-                        list.push(StreamCheckResult::new(url, Ok(UrlType::Stream(stream))));
+    let is_hls = playlist_decoder::is_content_hls(content);
+    if is_hls {
+        let playlist = MasterPlaylist::try_from(content);
+        match playlist{
+            Ok(playlist)=>{
+                if let Some(i) = playlist.variant_streams.first() {
+                    let mut audio = AudioCodec::Unknown;
+                    let mut video: Option<VideoCodec> = None;
+                    let codecs_obj = i.codecs();
+                    if let Some(codecs_obj) = codecs_obj {
+                        let (a,v) = codec::from_hls_codecs(&codecs_obj.to_string());
+                        audio = a;
+                        video = v;
                     }
+                    let stream = StreamInfo {
+                        Server: None,
+                        Public: None,
+                        IceAudioInfo: None,
+                        AudioInfo: None,
+                        Type: String::from(""),
+                        Name: None,
+                        Description: None,
+                        Homepage: None,
+                        Bitrate: Some((i.bandwidth() as u32) / 1000),
+                        Genre: None,
+                        Sampling: None,
+                        Channels: None,
+                        Quality: None,
+                        CodecAudio: audio,
+                        CodecVideo: video,
+                        VideoProfile: video.and_then(|video| video.profile()),
+                        Container: Container::Unknown,
+                        Hls: true,
+                        LogoUrl: None,
+                        MainStreamUrl: None,
+                        IcyVersion: 1,
+                        OverrideIndexMetaData: None,
+                        CountryCode: None,
+                        CountrySubdivisonCode: None,
+                        LanguageCodes: vec![],
+                        DoNotIndex: None,
+                        SslError: ssl_error,
+                        GeoLatLong: None,
+                        DecodeErrors: vec![],
+                        Loudness: None,
+                        OtherHeaders: HashMap::new(),
+                        HeaderConflicts: vec![],
+                        Dialect: HeaderDialect::None,
+                        ServerStatus: None,
+                        MetaInfoFile: None,
+                        FieldSources: HashMap::new(),
+                    };
+                    ctx.emit(|| CheckEvent::StreamFound { url: url.to_string(), info: Box::new(stream.clone()) });
+                    list.push(StreamCheckResult::new(url, Ok(UrlType::Stream(stream))));
                 }
-            }else{
-                let playlist = decode_playlist(url, &content);
-                match playlist {
-                    Ok(playlist) => {
-                        if playlist.len() == 0 {
-                            list.push(StreamCheckResult::new(url, Err(StreamCheckError::PlaylistEmpty())));
-                        } else {
-                            for playlist_item in playlist {
-                                // ignore self references
-                                if url == playlist_item {
-                                    warn!("found self reference in playlist: '{}'", url);
-                                    continue;
-                                }
-                                let result = check(&playlist_item, early_exit_on_first_ok, timeout, max_depth);
-                                let result_ok = result.info.is_ok();
-                                list.push(result);
-
-                                // early exit on the first found working stream
-                                if early_exit_on_first_ok && result_ok {
-                                    break;
-                                }
-                            }
-                        }
-                    },
-                    Err(err) => {
-                        list.push(StreamCheckResult::new(url, Err(err)));
-                    }
+            }
+            Err(_)=>{
+                let stream = StreamInfo {
+                    Server: None,
+                    Public: None,
+                    IceAudioInfo: None,
+                    AudioInfo: None,
+                    Type: String::from(""),
+                    Name: None,
+                    Description: None,
+                    Homepage: None,
+                    Bitrate: None,
+                    Genre: None,
+                    Sampling: None,
+                    Channels: None,
+                    Quality: None,
+                    CodecAudio: AudioCodec::Unknown,
+                    CodecVideo: None,
+                    VideoProfile: None,
+                    Container: Container::Unknown,
+                    Hls: true,
+                    LogoUrl: None,
+                    MainStreamUrl: None,
+                    IcyVersion: 1,
+                    OverrideIndexMetaData: None,
+                    CountryCode: None,
+                    CountrySubdivisonCode: None,
+                    LanguageCodes: vec![],
+                    DoNotIndex: None,
+                    SslError: ssl_error,
+                    GeoLatLong: None,
+                    DecodeErrors: vec![],
+                    Loudness: None,
+                    OtherHeaders: HashMap::new(),
+                    HeaderConflicts: vec![],
+                    Dialect: HeaderDialect::None,
+                    ServerStatus: None,
+                    MetaInfoFile: None,
+                    FieldSources: HashMap::new(),
+                };
+                ctx.emit(|| CheckEvent::StreamFound { url: url.to_string(), info: Box::new(stream.clone()) });
+                list.push(StreamCheckResult::new(url, Ok(UrlType::Stream(stream))));
+            }
+        }
+    }else{
+        let playlist = decode_playlist(url, content);
+        match playlist {
+            Ok(playlist) => {
+                if playlist.is_empty() {
+                    list.push(failed(url, StreamCheckError::PlaylistEmpty(), ctx));
+                } else {
+                    list.extend(check_playlist_entries(url, playlist, ctx, max_depth, visited));
                 }
+            },
+            Err(err) => {
+                list.push(failed(url, err, ctx));
             }
         }
-        Err(_err)=>{
-            list.push(StreamCheckResult::new(url, Err(StreamCheckError::PlaylistReadFailed())));
+    }
+    list
+}
+
+/// Check links to streams and playlists found in a html page
+fn handle_html(mut request: Request, url: &str, charset: &str, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> Vec<StreamCheckResult> {
+    if request.read_up_to(HTML_MAX_SIZE).is_err() {
+        let error = ctx.interrupted().unwrap_or(StreamCheckError::PlaylistReadFailed());
+        return vec![failed(url, error, ctx)];
+    }
+    let content = request.text(Some(charset).filter(|charset| !charset.is_empty()));
+    let base_url = match Url::parse(url) {
+        Ok(base_url) => base_url,
+        Err(_) => return vec![failed(url, StreamCheckError::UrlParseError(), ctx)],
+    };
+    let links: Vec<PlaylistEntry> = htmllinks::extract(&content)
+        .into_iter()
+        .filter_map(|link| base_url.join(&link).ok())
+        .map(|link| PlaylistEntry { url: link.to_string(), metadata: None })
+        .take(10)
+        .collect();
+    if links.is_empty() {
+        return vec![failed(url, StreamCheckError::PlaylistEmpty(), ctx)];
+    }
+    check_playlist_entries(url, links, ctx, max_depth, visited)
+}
+
+/// Check all entries of a playlist, one after another or in parallel
+fn check_playlist_entries(url: &str, playlist: Vec<PlaylistEntry>, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> Vec<StreamCheckResult> {
+    let mut list: Vec<StreamCheckResult> = vec![];
+    ctx.emit(|| CheckEvent::PlaylistDecoded { url: url.to_string(), entries: playlist.iter().map(|entry| entry.url.clone()).collect() });
+    // ignore self references
+    let playlist: Vec<PlaylistEntry> = playlist
+        .into_iter()
+        .filter(|playlist_item| {
+            let self_reference = same_url(url, &playlist_item.url);
+            if self_reference {
+                warn!("found self reference in playlist: '{}'", url);
+            }
+            !self_reference
+        })
+        .collect();
+    if ctx.options.playlist_concurrency > 1 {
+        list.extend(check_playlist_parallel(&playlist, ctx, max_depth, visited));
+    } else {
+        for playlist_item in playlist {
+            let result = check_playlist_entry(&playlist_item, ctx, max_depth, visited);
+            let result_ok = result.info.is_ok();
+            list.push(result);
+
+            // early exit on the first found working stream
+            if ctx.options.early_exit_on_first_ok && result_ok {
+                break;
+            }
         }
     }
     list
 }
 
-fn handle_stream(request: Request, Type: String, stream_type: String /* , deep_scan: bool */) -> StreamInfo {
+/// Check url of a playlist entry and attach the metadata of the entry.
+/// The title of the entry is used as name of the stream if the stream does not send one.
+fn check_playlist_entry(entry: &PlaylistEntry, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> StreamCheckResult {
+    let mut result = check(&entry.url, ctx, max_depth, visited);
+    if let Some(metadata) = &entry.metadata {
+        if let Some(title) = &metadata.title {
+            fill_name(&mut result, title);
+        }
+        result = result.with_playlist_metadata(metadata.clone());
+    }
+    result
+}
+
+fn fill_name(result: &mut StreamCheckResult, name: &str) {
+    match &mut result.info {
+        Ok(UrlType::Stream(info)) if info.Name.is_none() => info.Name = Some(name.to_string()),
+        Ok(UrlType::Redirect(item)) => fill_name(item, name),
+        _ => {}
+    }
+}
+
+/// Check playlist entries with a bounded number of threads, results keep the order of the playlist.
+/// With early exit, the checks still running are cancelled as soon as one entry works
+/// and only finished checks are returned.
+fn check_playlist_parallel(playlist: &[PlaylistEntry], ctx: &CheckContext, max_depth: u8, visited: &[String]) -> Vec<StreamCheckResult> {
+    let siblings = ctx.child();
+    let next = AtomicUsize::new(0);
+    let found = AtomicBool::new(false);
+    let results: Mutex<Vec<Option<StreamCheckResult>>> = Mutex::new(playlist.iter().map(|_| None).collect());
+    let workers = ctx.options.playlist_concurrency.min(playlist.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= playlist.len() || found.load(Ordering::SeqCst) {
+                    break;
+                }
+                let result = check_playlist_entry(&playlist[index], &siblings, max_depth, visited);
+                let result_ok = result.info.is_ok();
+                if ctx.options.early_exit_on_first_ok {
+                    if result_ok {
+                        if !found.swap(true, Ordering::SeqCst) {
+                            siblings.cancellation.cancel();
+                        }
+                    } else if found.load(Ordering::SeqCst) {
+                        // aborted because another entry works
+                        continue;
+                    }
+                }
+                if let Ok(mut results) = results.lock() {
+                    results[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap_or_default().into_iter().flatten().collect()
+}
+
+fn handle_stream(mut request: Request, url: &str, Type: String, mut container: Container, mut codec_audio: AudioCodec, ctx: &CheckContext) -> StreamInfo {
+    let options = ctx.options;
     debug!("handle_stream()");
 
     let ssl_error = request.had_ssl_error();
-    //if deep_scan {
-    //    let result = request.read_up_to(50);
-    //    if result.is_ok(){
-    //        let bytes = request.bytes();
-    //        let scan_result = streamdeepscan::scan(bytes);
-    //        if let Ok(scan_result) = scan_result {
-    //            if let Some(scan_result) = scan_result {
-    //                let x = type_is_stream(&scan_result.mime);
-    //                if let Some(x) = x {
-    //                    stream_type = String::from(x);
-    //                    debug!("url={}, override stream_type of with deep scan: {}", url, stream_type);
-    //                }
-    //            }
-    //        }
-    //    }
-    //}
-
-    let mut headers = request.info.headers;
-    let icy_pub: Option<bool> = match headers.get("icy-pub") {
-        Some(content) => {
-            let number = content.parse::<u32>();
-            match number {
-                Ok(number)=>{
-                    Some(number == 1)
-                },
-                Err(_) => {
-                    None
+    // content type does not tell the codec, look at the payload
+    if matches!(codec_audio, AudioCodec::Unknown | AudioCodec::Ogg | AudioCodec::Flv) {
+        let result = request.read_up_to(4096);
+        if result.is_ok() {
+            if let Some((scanned_container, scanned_codec)) = streamdeepscan::scan(request.bytes()) {
+                debug!("override stream type with deep scan: {} {}", scanned_container, scanned_codec);
+                if container == Container::Unknown {
+                    container = scanned_container;
+                }
+                if scanned_codec != AudioCodec::Unknown {
+                    codec_audio = scanned_codec;
                 }
             }
-        },
-        None => {
-            None
         }
+    }
+
+    let mut headers = std::mem::take(&mut request.info.headers);
+    let Dialect = if request.info.is_icy() {
+        HeaderDialect::ShoutcastV1
+    } else if headers.keys().any(|key| key.starts_with("icy-")) {
+        HeaderDialect::Icy
+    } else if headers.keys().any(|key| key.starts_with("x-audiocast-")) {
+        HeaderDialect::XAudiocast
+    } else {
+        HeaderDialect::None
     };
 
+    let mut DecodeErrors = vec![];
     let LanguageCodesString: Option<String> = headers.remove("icy-language-codes");
     let mut LanguageCodes: Vec<String> = vec![];
     if let Some(LanguageCodesSome) = LanguageCodesString {
-        for split_str in LanguageCodesSome.split(",") {
+        for split_str in LanguageCodesSome.split(',') {
             let split_str_trimmed = split_str.trim();
-            if split_str_trimmed != "" {
-                LanguageCodes.push(split_str_trimmed.to_string());
+            if !split_str_trimmed.is_empty() {
+                if let Some(code) = valid_code("icy-language-codes", isocodes::language_code(split_str_trimmed), &mut DecodeErrors) {
+                    if !LanguageCodes.contains(&code) {
+                        LanguageCodes.push(code);
+                    }
+                }
             }
         }
     }
+    let CountryCode = headers
+        .remove("icy-country-code")
+        .filter(|code| !code.trim().is_empty())
+        .and_then(|code| valid_code("icy-country-code", isocodes::country_code(&code), &mut DecodeErrors));
+    let CountrySubdivisonCode = headers
+        .remove("icy-country-subdivision-code")
+        .filter(|code| !code.trim().is_empty())
+        .and_then(|code| valid_code("icy-country-subdivision-code", isocodes::subdivision_code(&code), &mut DecodeErrors));
 
     trace!("headers: {:?}", headers);
 
-    let stream = StreamInfo {
+    let IceAudioInfo = headers.remove("ice-audio-info");
+    let AudioInfo = headers.remove("icy-audio-info");
+    let ice_params = IceAudioInfo.as_deref().map(AudioParameters::from).unwrap_or_default();
+    let icy_params = AudioInfo.as_deref().map(AudioParameters::from).unwrap_or_default();
+    let mut HeaderConflicts = vec![];
+    let Public = headerconflict::reconcile(
+        "Public",
+        vec![
+            ("icy-pub", headers.remove("icy-pub").and_then(|s| s.parse::<u32>().ok()).map(|n| n == 1)),
+            ("x-audiocast-public", headers.remove("x-audiocast-public").and_then(|s| s.parse::<u32>().ok()).map(|n| n == 1)),
+        ],
+        &mut HeaderConflicts,
+    );
+    let mut text_field = |field: &str, icy_header: &str, audiocast_header: &str| {
+        headerconflict::reconcile(
+            field,
+            vec![
+                (icy_header, headers.remove(icy_header)),
+                (audiocast_header, headers.remove(audiocast_header)),
+            ],
+            &mut HeaderConflicts,
+        )
+    };
+    let Name = text_field("Name", "icy-name", "x-audiocast-name");
+    let Description = text_field("Description", "icy-description", "x-audiocast-description");
+    let Homepage = text_field("Homepage", "icy-url", "x-audiocast-url");
+    let Genre = text_field("Genre", "icy-genre", "x-audiocast-genre");
+    // unparsable values are left out, so that the audio info headers can fill in
+    let Bitrate = headerconflict::reconcile(
+        "Bitrate",
+        vec![
+            ("icy-br", headers.remove("icy-br").and_then(|s| s.split(',').next().unwrap_or("").trim().parse().ok())),
+            ("x-audiocast-bitrate", headers.remove("x-audiocast-bitrate").and_then(|s| s.trim().parse().ok())),
+            ("ice-audio-info", ice_params.bitrate),
+            ("icy-audio-info", icy_params.bitrate),
+        ],
+        &mut HeaderConflicts,
+    );
+    let Sampling = headerconflict::reconcile(
+        "Sampling",
+        vec![
+            ("icy-sr", headers.remove("icy-sr").and_then(|s| s.trim().parse().ok())),
+            // some use different header
+            ("icy-samplerate", headers.remove("icy-samplerate").and_then(|s| s.trim().parse().ok())),
+            ("ice-audio-info", ice_params.samplerate),
+            ("icy-audio-info", icy_params.samplerate),
+        ],
+        &mut HeaderConflicts,
+    );
+    let Channels = headerconflict::reconcile(
+        "Channels",
+        vec![("ice-audio-info", ice_params.channels), ("icy-audio-info", icy_params.channels)],
+        &mut HeaderConflicts,
+    );
+    let Quality = headerconflict::reconcile(
+        "Quality",
+        vec![("ice-audio-info", ice_params.quality), ("icy-audio-info", icy_params.quality)],
+        &mut HeaderConflicts,
+    );
+
+    let mut stream = StreamInfo {
         Server: headers.remove("server"),
-        Public: icy_pub,
-        AudioInfo: headers.remove("icy-audio-info"),
-        IceAudioInfo: headers.remove("ice-audio-info"),
+        Public,
+        AudioInfo,
+        IceAudioInfo,
         Type,
-        Name: headers.remove("icy-name"),
-        Description: headers.remove("icy-description"),
-        Homepage: headers.remove("icy-url"),
-        Bitrate: headers
-            .remove("icy-br")
-            .map(|s| s.split(",").nth(0).unwrap_or("").parse().unwrap_or(0)),
-        Genre: headers.remove("icy-genre"),
-        Sampling: headers
-            .remove("icy-sr")
-            // some use different header
-            .or(headers.remove("icy-samplerate"))
-            .map(|s| s.parse().unwrap_or(0)),
-        CodecAudio: stream_type,
+        Name,
+        Description,
+        Homepage,
+        Bitrate,
+        Genre,
+        Sampling,
+        Channels,
+        Quality,
+        CodecAudio: codec_audio,
         CodecVideo: None,
+        VideoProfile: None,
+        Container: container,
         Hls: false,
         LogoUrl: headers.remove("icy-logo"),
         MainStreamUrl: headers.remove("icy-main-stream-url"),
@@ -326,126 +544,221 @@ fn handle_stream(request: Request, Type: String, stream_type: String /* , deep_s
         OverrideIndexMetaData: headers
             .remove("icy-index-metadata")
             .map(|s| s.parse().unwrap_or(0) == 1),
-        CountryCode: headers.remove("icy-country-code"),
-        CountrySubdivisonCode: headers.remove("icy-country-subdivision-code"),
+        CountryCode,
+        CountrySubdivisonCode,
         LanguageCodes,
-        GeoLatLong: headers.remove("icy-geo-lat-long").map(|x| LatLong::try_from(x)),
+        GeoLatLong: headers
+            .remove("icy-geo-lat-long")
+            .map(|value| LatLong::try_from(value).map_err(|err| err.with_field("icy-geo-lat-long"))),
+        DecodeErrors,
         DoNotIndex: headers
             .remove("icy-do-not-index")
             .map(|s| s.parse().unwrap_or(0) == 1),
         SslError: ssl_error,
+        Loudness: None,
+        OtherHeaders: HashMap::new(),
+        HeaderConflicts,
+        Dialect,
+        ServerStatus: None,
+        MetaInfoFile: None,
+        FieldSources: HashMap::new(),
     };
+    // keep all headers that were not used above
+    stream.OtherHeaders = headers;
+
+    if options.meta_info_file {
+        if let Some(homepage) = stream.Homepage.clone() {
+            let result = ctx
+                .meta_info_cache
+                .get_or_fetch(&homepage, || http_config::fetch(&homepage, ctx.timeout(), Some(&ctx.cancellation)));
+            if let Ok(file) = &result {
+                file.merge_into(&mut stream);
+            }
+            stream.MetaInfoFile = Some(result);
+        }
+    }
+
+    if options.server_status {
+        stream.ServerStatus = Some(match serverstatus::detect_server_type(&stream) {
+            Some(server_type) => serverstatus::fetch(url, server_type, ctx.timeout(), Some(&ctx.cancellation)),
+            None => Err(ServerStatusError::UnknownServer()),
+        });
+    }
+
+    if let Some(seconds) = options.loudness_seconds {
+        stream.Loudness = Some(loudness::measure(request.into_reader(), &stream.Type, seconds, &ctx.cancellation));
+    }
 
     stream
 }
 
-pub fn check(url: &str, early_exit_on_first_ok: bool, timeout: u32, max_depth: u8) -> StreamCheckResult {
+/// Value of a valid code, invalid codes are recorded
+fn valid_code(header: &str, code: Result<String, DecodeError>, errors: &mut Vec<DecodeError>) -> Option<String> {
+    code.map_err(|err| {
+        let err = err.with_field(header);
+        debug!("invalid code: {}", err);
+        errors.push(err);
+    })
+    .ok()
+}
+
+/// Check url, `visited` are the urls of redirects and playlists which lead to it
+pub fn check(url: &str, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> StreamCheckResult {
     debug!("check(url={})",url);
+    ctx.emit(|| CheckEvent::Started { url: url.to_string() });
+    let result = check_url(url, ctx, max_depth, visited);
+    match &result.info {
+        Ok(UrlType::Stream(info)) => ctx.emit(|| CheckEvent::StreamFound { url: url.to_string(), info: Box::new(info.clone()) }),
+        Err(error) => ctx.emit(|| CheckEvent::Error { url: url.to_string(), error: error.clone() }),
+        _ => {}
+    }
+    result
+}
+
+/// Result for a failure which does not pass through `check`, observers get an `Error` event for it
+fn failed(url: &str, error: StreamCheckError, ctx: &CheckContext) -> StreamCheckResult {
+    ctx.emit(|| CheckEvent::Error { url: url.to_string(), error: error.clone() });
+    StreamCheckResult::new(url, Err(error))
+}
+
+/// Check playlist content which was not downloaded from `url`, relative entries are resolved against `url`
+pub fn check_content(content: &str, url: &str, ctx: &CheckContext, max_depth: u8) -> StreamCheckResult {
+    debug!("check_content(url={})", url);
+    ctx.emit(|| CheckEvent::Started { url: url.to_string() });
+    if let Some(error) = ctx.interrupted() {
+        return failed(url, error, ctx);
+    }
+    if max_depth == 0 {
+        return failed(url, StreamCheckError::MaxDepthReached(), ctx);
+    }
+    let visited = vec![url.to_string()];
+    let format = playlistformat::sniff(content.as_bytes());
+    if format == Some(PlaylistFormat::Mpd) {
+        return failed(url, StreamCheckError::UnsupportedPlaylistFormat(PlaylistFormat::Mpd), ctx).with_playlist_format(PlaylistFormat::Mpd);
+    }
+    let result = StreamCheckResult::new(url, Ok(UrlType::PlayList(handle_playlist_content(content, false, url, ctx, max_depth - 1, &visited))));
+    match format {
+        Some(format) => result.with_playlist_format(format),
+        None => result,
+    }
+}
+
+fn check_url(url: &str, ctx: &CheckContext, max_depth: u8, visited: &[String]) -> StreamCheckResult {
+    if let Some(error) = ctx.interrupted() {
+        return StreamCheckResult::new(url, Err(error));
+    }
+    if visited.iter().any(|visited_url| same_url(visited_url, url)) {
+        return StreamCheckResult::new(url, Err(StreamCheckError::RedirectLoop(url.to_string())));
+    }
     if max_depth == 0{
         return StreamCheckResult::new(url, Err(StreamCheckError::MaxDepthReached()));
     }
-    let request = Request::new(&url, "StreamCheckBot/0.1.0", timeout);
+    let mut visited = visited.to_vec();
+    visited.push(url.to_string());
+    let request = Request::new(url, "StreamCheckBot/0.1.0", ctx.timeout(), Some(&ctx.cancellation));
     match request {
         Ok(mut request) => {
-            if request.info.code >= 200 && request.info.code < 300 {
-                let content_type = request.info.headers.remove("content-type");
-                let content_length = request.content_length().ok();
-                match content_type {
-                    Some(content_type) => {
-                        let (content_type, content_charset) = decode_content_type(&content_type);
-                        let link_type = get_type(&content_type, &content_charset, content_length);
-                        match link_type {
-                            LinkType::Playlist(_charset) => StreamCheckResult::new(url, Ok(UrlType::PlayList(handle_playlist(request, url, early_exit_on_first_ok, timeout, max_depth - 1)))),
-                            LinkType::Stream(stream_type) => StreamCheckResult::new(url, Ok(UrlType::Stream(handle_stream(request, content_type.to_string(), stream_type)))),
-                            _ => StreamCheckResult::new(url, Err(StreamCheckError::UnknownContentType(content_type.to_string())))
-                        }
+            let status = request.info.code;
+            let result = if request.info.code >= 200 && request.info.code < 300 {
+                let content_type_header = request.info.headers.remove("content-type");
+                let (content_type, content_charset) = decode_content_type(content_type_header.as_deref().unwrap_or_default());
+                // first bytes tell playlists apart from streams, stream types are trusted
+                // to not read from streams which are checked later anyway
+                let sniffed = if codec::from_mime(&content_type, false).is_none() {
+                    let _ = request.read_up_to(SNIFF_SIZE);
+                    playlistformat::sniff(request.bytes())
+                } else {
+                    None
+                };
+                if content_type_header.is_none() && sniffed.is_none() {
+                    StreamCheckResult::new(url, Err(StreamCheckError::MissingContentType()))
+                } else {
+                    let link_type = get_type(&content_type, &content_charset, sniffed, ctx.options.html_links);
+                    match link_type {
+                        LinkType::Playlist(_charset, PlaylistFormat::Mpd) => StreamCheckResult::new(url, Err(StreamCheckError::UnsupportedPlaylistFormat(PlaylistFormat::Mpd))).with_playlist_format(PlaylistFormat::Mpd),
+                        LinkType::Playlist(charset, format) => StreamCheckResult::new(url, Ok(UrlType::PlayList(handle_playlist(request, url, &charset, ctx, max_depth - 1, &visited)))).with_playlist_format(format),
+                        LinkType::Html(charset) => StreamCheckResult::new(url, Ok(UrlType::PlayList(handle_html(request, url, &charset, ctx, max_depth - 1, &visited)))).with_playlist_format(PlaylistFormat::Html),
+                        LinkType::Stream(container, codec) => StreamCheckResult::new(url, Ok(UrlType::Stream(handle_stream(request, url, content_type.to_string(), container, codec, ctx)))),
+                        _ => StreamCheckResult::new(url, Err(StreamCheckError::UnknownContentType(content_type.to_string())))
                     }
-                    None => StreamCheckResult::new(url, Err(StreamCheckError::MissingContentType()))
                 }
             } else if request.info.code >= 300 && request.info.code < 400 {
                 let location = request.info.headers.get("location");
                 match location {
-                    Some(location) => StreamCheckResult::new(url, Ok(UrlType::Redirect(Box::new(check(location,early_exit_on_first_ok, timeout,max_depth - 1))))),
+                    Some(location) => match resolve_location(url, location) {
+                        Ok(location) => {
+                            ctx.emit(|| CheckEvent::Redirect { url: url.to_string(), location: location.clone(), status: request.info.code });
+                            StreamCheckResult::new(url, Ok(UrlType::Redirect(Box::new(check(&location, ctx, max_depth - 1, &visited)))))
+                        }
+                        Err(err) => StreamCheckResult::new(url, Err(err)),
+                    },
                     None => StreamCheckResult::new(url, Err(StreamCheckError::NoLocationFieldForRedirect()))
                 }
             } else {
                 StreamCheckResult::new(url, Err(StreamCheckError::IllegalStatusCode(request.info.code)))
-            }
+            };
+            result.with_status(status)
         }
-        Err(_err) => StreamCheckResult::new(url, Err(StreamCheckError::ConnectionFailed())),
+        Err(_err) => {
+            let error = ctx.interrupted().unwrap_or(StreamCheckError::ConnectionFailed());
+            StreamCheckResult::new(url, Err(error))
+        }
+    }
+}
+
+/// Urls which only differ in case of the host, default port or fragment lead to the same resource
+fn same_url(a: &str, b: &str) -> bool {
+    let normalize = |url: &str| Url::parse(url).ok().map(|mut url| {
+        url.set_fragment(None);
+        url
+    });
+    match (normalize(a), normalize(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
     }
 }
 
-/// Decode playlist to list of urls
+/// Location header may be relative, resolve it with the url of the request as base
+fn resolve_location(url_str: &str, location: &str) -> Result<String, StreamCheckError> {
+    let base_url = Url::parse(url_str).or(Err(StreamCheckError::UrlParseError()))?;
+    Ok(base_url.join(location.trim()).or(Err(StreamCheckError::UrlJoinError()))?.to_string())
+}
+
+/// Decode playlist to list of urls with their metadata
 /// Resolve relative urls in playlist with original url as base
-fn decode_playlist(url_str: &str, content: &str) -> Result<Vec<String>, StreamCheckError> {
+fn decode_playlist(url_str: &str, content: &str) -> Result<Vec<PlaylistEntry>, StreamCheckError> {
     let mut list = vec![];
     let base_url = Url::parse(url_str).or(Err(StreamCheckError::UrlParseError()))?;
     let urls = playlist_decoder::decode(content).or(Err(StreamCheckError::PlayListDecodeError()))?;
+    let mut metadata = playlistmetadata::decode(content);
     let mut max_urls = 10;
     for url in urls {
         if max_urls == 0 {
             break;
         }
         if url.trim() != "" {
-            list.push(base_url.join(&url).or(Err(StreamCheckError::UrlJoinError()))?.to_string());
-            max_urls = max_urls - 1;
+            list.push(PlaylistEntry {
+                url: base_url.join(&url).or(Err(StreamCheckError::UrlJoinError()))?.to_string(),
+                metadata: metadata.remove(url.trim()),
+            });
+            max_urls -= 1;
         }
     }
 
     Ok(list)
 }
 
-fn decode_hls_codecs(codecs_raw: &str) -> (String,Option<String>) {
-    // codec information from
-    // https://developer.apple.com/library/content/documentation/NetworkingInternet/Conceptual/StreamingMediaGuide/FrequentlyAskedQuestions/FrequentlyAskedQuestions.html
+#[cfg(test)]
+mod tests {
+    use super::same_url;
 
-    let mut codec_audio: String = String::from("UNKNOWN");
-    let mut codec_video: Option<String> = None;
-
-    if codecs_raw.contains("mp4a.40.2") {
-        // AAC-LC
-        codec_audio = String::from("AAC");
-    }
-    if codecs_raw.contains("mp4a.40.5") {
-        // HE-AAC
-        codec_audio = String::from("AAC+");
-    }
-    if codecs_raw.contains("mp4a.40.34") {
-        codec_audio = String::from("MP3");
-    }
-    if codecs_raw.contains("avc1.42001e") || codecs_raw.contains("avc1.66.30") {
-        // H.264 Baseline Profile level 3.0
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.42001f") {
-        // H.264 Baseline Profile level 3.1
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.4d001e") || codecs_raw.contains("avc1.77.30") {
-        // H.264 Main Profile level 3.0
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.4d001f") {
-        // H.264 Main Profile level 3.1
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.4d0028") {
-        // H.264 Main Profile level 4.0
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.64001f") {
-        // H.264 High Profile level 3.1
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.640028") {
-        // H.264 High Profile level 4.0
-        codec_video = Some(String::from("H.264"));
-    }
-    if codecs_raw.contains("avc1.640029") {
-        // H.264 High Profile level 4.1
-        codec_video = Some(String::from("H.264"));
+    #[test]
+    fn same_url_normalized() {
+        for url in ["http://A/x", "http://a:80/x", "http://a/x#f"] {
+            assert!(same_url("http://a/x", url), "{}", url);
+        }
+        assert!(!same_url("http://a/x", "http://a/y"));
+        assert!(!same_url("http://a/x", "https://a/x"));
+        assert!(same_url("not a url", "not a url"));
     }
-
-    return (codec_audio,codec_video);
-}
\ No newline at end of file
+}
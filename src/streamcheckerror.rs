@@ -1,6 +1,8 @@
 use std::error::Error;
 use std::fmt;
 
+use crate::PlaylistFormat;
+
 use serde::{Deserialize, Serialize};
 
 /// Posible errors for stream checking.
@@ -18,6 +20,10 @@ pub enum StreamCheckError {
     UrlJoinError(),
     UrlParseError(),
     NoLocationFieldForRedirect(),
+    Cancelled(),
+    DeadlineExceeded(),
+    RedirectLoop(String),
+    UnsupportedPlaylistFormat(PlaylistFormat),
 }
 
 impl fmt::Display for StreamCheckError {
@@ -34,6 +40,10 @@ impl fmt::Display for StreamCheckError {
             StreamCheckError::UrlJoinError() => write!(f, "UrlJoinError()"),
             StreamCheckError::UrlParseError() => write!(f, "UrlParseError()"),
             StreamCheckError::NoLocationFieldForRedirect() => write!(f, "NoLocationFieldForRedirect()"),
+            StreamCheckError::Cancelled() => write!(f, "Cancelled()"),
+            StreamCheckError::DeadlineExceeded() => write!(f, "DeadlineExceeded()"),
+            StreamCheckError::RedirectLoop(url) => write!(f, "RedirectLoop({})", url),
+            StreamCheckError::UnsupportedPlaylistFormat(format) => write!(f, "UnsupportedPlaylistFormat({:?})", format),
         }
     }
 }
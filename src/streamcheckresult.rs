@@ -1,7 +1,14 @@
+use crate::resulttree::Nodes;
+use crate::FoundError;
+use crate::FoundStream;
+use crate::PlaylistFormat;
+use crate::PlaylistMetadata;
+use crate::Redirect;
 use crate::StreamCheckError;
 use crate::StreamInfo;
 use serde::{Deserialize, Serialize};
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum UrlType {
     Stream(StreamInfo),
@@ -14,6 +21,15 @@ pub enum UrlType {
 pub struct StreamCheckResult {
     url: String,
     pub info: Result<UrlType, StreamCheckError>,
+    /// Http status code of the response, None if there was no response
+    #[serde(default)]
+    status: Option<u32>,
+    /// Information from the playlist entry which contained this url
+    #[serde(default)]
+    playlist_metadata: Option<PlaylistMetadata>,
+    /// Detected format if this url is a playlist
+    #[serde(default)]
+    playlist_format: Option<PlaylistFormat>,
 }
 
 impl StreamCheckResult {
@@ -21,10 +37,111 @@ impl StreamCheckResult {
         StreamCheckResult {
             url: url.to_string(),
             info,
+            status: None,
+            playlist_metadata: None,
+            playlist_format: None,
         }
     }
 
+    pub fn with_status(mut self, status: u32) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_playlist_metadata(mut self, metadata: PlaylistMetadata) -> Self {
+        self.playlist_metadata = Some(metadata);
+        self
+    }
+
+    pub fn with_playlist_format(mut self, format: PlaylistFormat) -> Self {
+        self.playlist_format = Some(format);
+        self
+    }
+
     pub fn url(&self) -> &str {
         &self.url
     }
+
+    pub fn status(&self) -> Option<u32> {
+        self.status
+    }
+
+    pub fn playlist_metadata(&self) -> Option<&PlaylistMetadata> {
+        self.playlist_metadata.as_ref()
+    }
+
+    pub fn playlist_format(&self) -> Option<PlaylistFormat> {
+        self.playlist_format
+    }
+
+    /// Redirects which were followed from this url, in order
+    ///
+    /// # Example
+    /// ```no_run
+    /// let item = av_stream_info_rust::check_tree("http://example.com/stream", 10, 3, 3, true);
+    /// for redirect in item.redirect_chain() {
+    ///     println!("{} -> {} ({})", redirect.url, redirect.location, redirect.status);
+    /// }
+    /// ```
+    pub fn redirect_chain(&self) -> Vec<Redirect> {
+        let mut chain = vec![];
+        let mut current = self;
+        while let Ok(UrlType::Redirect(next)) = &current.info {
+            chain.push(Redirect {
+                url: current.url.clone(),
+                location: next.url.clone(),
+                status: current.status.unwrap_or(0),
+                permanent: current.status.map(Redirect::is_permanent_status).unwrap_or(false),
+            });
+            current = next;
+        }
+        chain
+    }
+
+    /// Final location of the permanent redirects at the start of the redirect chain.
+    /// This is the url which should replace a stored url, None if the first redirect
+    /// is temporary or there is none.
+    pub fn permanent_location(&self) -> Option<String> {
+        self.redirect_chain()
+            .into_iter()
+            .take_while(|redirect| redirect.permanent)
+            .last()
+            .map(|redirect| redirect.location)
+    }
+
+    /// Depth first iterator over this result and all results below it
+    ///
+    /// # Example
+    /// ```no_run
+    /// let item = av_stream_info_rust::check_tree("https://example.com/test.m3u", 10, 3, 3, false);
+    /// for node in item.nodes() {
+    ///     println!("{} {:?}", node.path.join(" > "), node.result.status());
+    /// }
+    /// ```
+    pub fn nodes(&self) -> Nodes<'_> {
+        Nodes::new(self)
+    }
+
+    /// First working stream in depth first order
+    ///
+    /// # Example
+    /// ```no_run
+    /// let item = av_stream_info_rust::check_tree("https://example.com/test.m3u", 10, 3, 3, true);
+    /// if let Some(stream) = item.first_ok_stream() {
+    ///     println!("{} {:?}", stream.url, stream.info.CodecAudio);
+    /// }
+    /// ```
+    pub fn first_ok_stream(&self) -> Option<FoundStream<'_>> {
+        self.nodes().find_map(|node| node.into_stream())
+    }
+
+    /// All working streams in depth first order
+    pub fn ok_streams(&self) -> Vec<FoundStream<'_>> {
+        self.nodes().filter_map(|node| node.into_stream()).collect()
+    }
+
+    /// All errors in depth first order
+    pub fn errors(&self) -> Vec<FoundError<'_>> {
+        self.nodes().filter_map(|node| node.into_error()).collect()
+    }
 }
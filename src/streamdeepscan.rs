@@ -1,17 +1,161 @@
-//use tree_magic;
-//
-//use log::{debug};
-//
-//pub struct ScanResult{
-//    pub mime: String
-//}
-//
-//pub fn scan(bytes: &[u8]) -> Result<Option<ScanResult>, Box<dyn std::error::Error>> {
-//    let mime = tree_magic::from_u8(bytes);
-//    debug!("found mime type: {}", mime);
-//    if mime != "application/octet-stream" {
-//        Ok(Some(ScanResult{mime}))
-//    }else{
-//        Ok(None)
-//    }
-//}
\ No newline at end of file
+use crate::codec::{AacProfile, AudioCodec, Container};
+
+/// Guess container and audio codec from the first bytes of a stream
+pub fn scan(bytes: &[u8]) -> Option<(Container, AudioCodec)> {
+    if bytes.starts_with(b"OggS") {
+        let codec = if contains(bytes, b"OpusHead") {
+            AudioCodec::Opus
+        } else if contains(bytes, b"\x01vorbis") {
+            AudioCodec::Vorbis
+        } else if contains(bytes, b"\x7fFLAC") {
+            AudioCodec::Flac
+        } else {
+            AudioCodec::Ogg
+        };
+        return Some((Container::Ogg, codec));
+    }
+    if bytes.starts_with(b"fLaC") {
+        return Some((Container::Flac, AudioCodec::Flac));
+    }
+    if bytes.starts_with(b"FLV") {
+        return Some((Container::Flv, AudioCodec::Flv));
+    }
+    if bytes.len() > 188 && bytes[0] == 0x47 && bytes[188] == 0x47 {
+        return Some((Container::MpegTs, AudioCodec::Unknown));
+    }
+    if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        return Some((Container::Mp4, AudioCodec::Unknown));
+    }
+
+    let mut bytes = bytes;
+    if bytes.starts_with(b"ID3") && bytes.len() >= 10 {
+        // size of id3 tag is stored as syncsafe integer
+        let size = bytes[6..10].iter().fold(0usize, |size, b| (size << 7) | (*b & 0x7f) as usize);
+        if bytes.len() < 10 + size {
+            // tag is larger than the scanned bytes, id3 tags are used with mp3
+            return Some((Container::Mpeg, AudioCodec::Mp3));
+        }
+        bytes = &bytes[10 + size..];
+    }
+    scan_frame_sync(bytes)
+}
+
+/// Bitrates in kbit/s of MPEG 1 layer I, II, III and MPEG 2/2.5 layer I, II/III
+const MPEG_BITRATES: [[u32; 15]; 5] = [
+    [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+    [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// Sampling rates of MPEG 1, the other versions use a half or a quarter of them
+const MPEG_SAMPLING_RATES: [u32; 3] = [44100, 48000, 32000];
+
+/// Search for the first MPEG audio or ADTS frame header which is followed by
+/// another frame header of the same kind, a single sync word is too likely to
+/// show up in random data
+fn scan_frame_sync(bytes: &[u8]) -> Option<(Container, AudioCodec)> {
+    for offset in 0..bytes.len() {
+        let (container, codec, length) = match frame_header(&bytes[offset..]) {
+            Some(header) => header,
+            None => continue,
+        };
+        let next = bytes.get(offset + length..).and_then(frame_header);
+        if let Some((next_container, next_codec, _)) = next {
+            if next_container == container && next_codec == codec {
+                return Some((container, codec));
+            }
+        }
+    }
+    None
+}
+
+/// Container, codec and frame length of the frame header at the start of bytes
+fn frame_header(bytes: &[u8]) -> Option<(Container, AudioCodec, usize)> {
+    if bytes.len() < 7 || bytes[0] != 0xff || bytes[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+    let layer = (bytes[1] >> 1) & 0x03;
+    if bytes[1] & 0xf0 == 0xf0 && layer == 0 {
+        let sampling_index = (bytes[2] >> 2) & 0x0f;
+        let length = ((bytes[3] as usize & 0x03) << 11) | ((bytes[4] as usize) << 3) | (bytes[5] as usize >> 5);
+        if sampling_index > 12 || length < 7 {
+            return None;
+        }
+        // ADTS only carries the base profile, HE-AAC is signaled implicitly
+        return Some((Container::Adts, AudioCodec::Aac(AacProfile::Lc), length));
+    }
+
+    let version = (bytes[1] >> 3) & 0x03;
+    let bitrate_index = (bytes[2] >> 4) as usize;
+    let sampling_index = ((bytes[2] >> 2) & 0x03) as usize;
+    let padding = ((bytes[2] >> 1) & 0x01) as usize;
+    // reserved values, and free format which has no computable frame length
+    if version == 1 || layer == 0 || bitrate_index == 0 || bitrate_index == 15 || sampling_index == 3 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let table = match (mpeg1, layer) {
+        (true, 3) => 0,
+        (true, 2) => 1,
+        (true, _) => 2,
+        (false, 3) => 3,
+        (false, _) => 4,
+    };
+    let bitrate = MPEG_BITRATES[table][bitrate_index] as usize * 1000;
+    let sampling_rate = match version {
+        3 => MPEG_SAMPLING_RATES[sampling_index],
+        2 => MPEG_SAMPLING_RATES[sampling_index] / 2,
+        _ => MPEG_SAMPLING_RATES[sampling_index] / 4,
+    } as usize;
+    let length = match layer {
+        3 => (12 * bitrate / sampling_rate + padding) * 4,
+        1 if !mpeg1 => 72 * bitrate / sampling_rate + padding,
+        _ => 144 * bitrate / sampling_rate + padding,
+    };
+    let codec = if layer == 1 { AudioCodec::Mp3 } else { AudioCodec::Unknown };
+    Some((Container::Mpeg, codec, length))
+}
+
+fn contains(bytes: &[u8], needle: &[u8]) -> bool {
+    bytes.windows(needle.len()).any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG 1 layer III, 128 kbit/s, 44.1 kHz: 417 bytes per frame
+    fn mp3_frame() -> Vec<u8> {
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        frame
+    }
+
+    /// ADTS AAC-LC frame of 100 bytes
+    fn adts_frame() -> Vec<u8> {
+        let mut frame = vec![0; 100];
+        frame[..7].copy_from_slice(&[0xff, 0xf1, 0x50, 0x80, 0x0c, 0x9f, 0xfc]);
+        frame
+    }
+
+    #[test]
+    fn frame_sync() {
+        let mp3 = mp3_frame().repeat(2);
+        assert_eq!(scan(&mp3), Some((Container::Mpeg, AudioCodec::Mp3)));
+
+        let mut garbage_then_mp3 = vec![0x12, 0xff, 0xe3, 0x00];
+        garbage_then_mp3.extend(mp3_frame().repeat(2));
+        assert_eq!(scan(&garbage_then_mp3), Some((Container::Mpeg, AudioCodec::Mp3)));
+
+        let adts = adts_frame().repeat(2);
+        assert_eq!(scan(&adts), Some((Container::Adts, AudioCodec::Aac(AacProfile::Lc))));
+
+        // a single sync word without a following frame
+        assert_eq!(scan(&mp3_frame()), None);
+        let mut lone_sync = vec![0; 1000];
+        lone_sync[10..14].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        assert_eq!(scan(&lone_sync), None);
+    }
+}
@@ -1,11 +1,26 @@
 #![allow(non_snake_case)]
 
+use crate::AudioCodec;
+use crate::Container;
 use crate::DecodeError;
+use crate::H264Profile;
+use crate::HeaderConflict;
+use crate::HeaderDialect;
 use crate::LatLong;
+use crate::FieldSource;
+use crate::Loudness;
+use crate::LoudnessError;
+use crate::MetaInfoError;
+use crate::MetaInfoFile;
+use crate::ServerStatus;
+use crate::ServerStatusError;
+use crate::VideoCodec;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-/// Information extracted from a stream
+/// Information extracted from a stream.
+/// Use [`StreamRecord`](crate::StreamRecord) for storing results in a stable format.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamInfo {
     pub Server: Option<String>,
@@ -19,8 +34,17 @@ pub struct StreamInfo {
     pub Genre: Option<String>,
     pub Bitrate: Option<u32>,
     pub Sampling: Option<u32>,
-    pub CodecAudio: String,
-    pub CodecVideo: Option<String>,
+    /// Number of audio channels from "ice-audio-info" or "icy-audio-info"
+    pub Channels: Option<u32>,
+    /// Encoder quality from "ice-audio-info" or "icy-audio-info"
+    pub Quality: Option<f64>,
+    pub CodecAudio: AudioCodec,
+    pub CodecVideo: Option<VideoCodec>,
+    /// Profile of H.264 video from the codecs of HLS variant streams
+    #[serde(default)]
+    pub VideoProfile: Option<H264Profile>,
+    #[serde(default)]
+    pub Container: Container,
     pub Hls: bool,
 
     pub LogoUrl: Option<String>,
@@ -31,6 +55,28 @@ pub struct StreamInfo {
     pub CountrySubdivisonCode: Option<String>,
     pub LanguageCodes: Vec<String>,
     pub GeoLatLong: Option<Result<LatLong, DecodeError>>,
+    /// Country and language codes which were rejected, the fields only contain valid codes
+    #[serde(default)]
+    pub DecodeErrors: Vec<DecodeError>,
     pub DoNotIndex: Option<bool>,
     pub SslError: bool,
+    /// Loudness of the stream, only measured if enabled in the check options
+    pub Loudness: Option<Result<Loudness, LoudnessError>>,
+    /// Response headers which are not decoded into other fields, keys are lowercase
+    #[serde(default)]
+    pub OtherHeaders: HashMap<String, String>,
+    /// Headers which contained different values for the same field
+    #[serde(default)]
+    pub HeaderConflicts: Vec<HeaderConflict>,
+    /// Family of metadata headers the server did send
+    #[serde(default)]
+    pub Dialect: HeaderDialect,
+    /// Status of the streaming server, only fetched if enabled in the check options
+    pub ServerStatus: Option<Result<ServerStatus, ServerStatusError>>,
+    /// Stream information file of the homepage, only fetched if enabled in the check options
+    #[serde(default)]
+    pub MetaInfoFile: Option<Result<MetaInfoFile, MetaInfoError>>,
+    /// Source of the fields which were merged with the stream information file
+    #[serde(default)]
+    pub FieldSources: HashMap<String, FieldSource>,
 }
@@ -0,0 +1,167 @@
+use crate::AudioCodec;
+use crate::Container;
+use crate::DecodeError;
+use crate::H264Profile;
+use crate::HeaderConflict;
+use crate::HeaderDialect;
+use crate::LatLong;
+use crate::FieldSource;
+use crate::Loudness;
+use crate::LoudnessError;
+use crate::MetaInfoError;
+use crate::MetaInfoFile;
+use crate::ServerStatus;
+use crate::ServerStatusError;
+use crate::StreamInfo;
+use crate::VideoCodec;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Information extracted from a stream with snake case field names and an explicit
+/// schema version, intended for storing results.
+///
+/// Serialized data of the legacy [`StreamInfo`] can also be deserialized into this type,
+/// `schema_version` is 0 in that case.
+///
+/// # Example
+/// ```rust
+/// use av_stream_info_rust::StreamRecord;
+///
+/// let legacy = r#"{"Server":null,"Public":null,"IceAudioInfo":null,"AudioInfo":null,
+///     "Name":"Test","Description":null,"Type":"audio/mpeg","Homepage":null,"Genre":null,
+///     "Bitrate":128,"Sampling":null,"CodecAudio":"MP3","CodecVideo":null,"Hls":false,
+///     "LogoUrl":null,"MainStreamUrl":null,"IcyVersion":1,"OverrideIndexMetaData":null,
+///     "CountryCode":"DE","CountrySubdivisonCode":"DE-BY","LanguageCodes":[],
+///     "GeoLatLong":null,"DoNotIndex":null,"SslError":false}"#;
+/// let record: StreamRecord = serde_json::from_str(legacy).unwrap();
+/// assert_eq!(record.schema_version, 0);
+/// assert_eq!(record.country_subdivision_code.as_deref(), Some("DE-BY"));
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamRecord {
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(alias = "Server")]
+    pub server: Option<String>,
+    #[serde(alias = "Public")]
+    pub public: Option<bool>,
+    #[serde(alias = "IceAudioInfo")]
+    pub ice_audio_info: Option<String>,
+    #[serde(alias = "AudioInfo")]
+    pub icy_audio_info: Option<String>,
+    #[serde(alias = "Name")]
+    pub name: Option<String>,
+    #[serde(alias = "Description")]
+    pub description: Option<String>,
+    #[serde(alias = "Type")]
+    pub content_type: String,
+    #[serde(alias = "Homepage")]
+    pub homepage: Option<String>,
+    #[serde(alias = "Genre")]
+    pub genre: Option<String>,
+    #[serde(alias = "Bitrate")]
+    pub bitrate: Option<u32>,
+    #[serde(alias = "Sampling")]
+    pub sampling: Option<u32>,
+    #[serde(alias = "Channels")]
+    pub channels: Option<u32>,
+    #[serde(alias = "Quality")]
+    pub quality: Option<f64>,
+    #[serde(alias = "CodecAudio")]
+    pub codec_audio: AudioCodec,
+    #[serde(alias = "CodecVideo")]
+    pub codec_video: Option<VideoCodec>,
+    #[serde(alias = "VideoProfile", default)]
+    pub video_profile: Option<H264Profile>,
+    #[serde(alias = "Container", default)]
+    pub container: Container,
+    #[serde(alias = "Hls")]
+    pub hls: bool,
+
+    #[serde(alias = "LogoUrl")]
+    pub logo_url: Option<String>,
+    #[serde(alias = "MainStreamUrl")]
+    pub main_stream_url: Option<String>,
+    #[serde(alias = "IcyVersion")]
+    pub icy_version: u32,
+    #[serde(alias = "OverrideIndexMetaData")]
+    pub override_index_metadata: Option<bool>,
+    #[serde(alias = "CountryCode")]
+    pub country_code: Option<String>,
+    #[serde(alias = "CountrySubdivisonCode")]
+    pub country_subdivision_code: Option<String>,
+    #[serde(alias = "LanguageCodes", default)]
+    pub language_codes: Vec<String>,
+    #[serde(alias = "GeoLatLong")]
+    pub geo_lat_long: Option<Result<LatLong, DecodeError>>,
+    #[serde(alias = "DecodeErrors", default)]
+    pub decode_errors: Vec<DecodeError>,
+    #[serde(alias = "DoNotIndex")]
+    pub do_not_index: Option<bool>,
+    #[serde(alias = "SslError")]
+    pub ssl_error: bool,
+    #[serde(alias = "Loudness")]
+    pub loudness: Option<Result<Loudness, LoudnessError>>,
+    #[serde(alias = "OtherHeaders", default)]
+    pub other_headers: HashMap<String, String>,
+    #[serde(alias = "HeaderConflicts", default)]
+    pub header_conflicts: Vec<HeaderConflict>,
+    #[serde(alias = "Dialect", default)]
+    pub dialect: HeaderDialect,
+    #[serde(alias = "ServerStatus")]
+    pub server_status: Option<Result<ServerStatus, ServerStatusError>>,
+    #[serde(alias = "MetaInfoFile", default)]
+    pub meta_info_file: Option<Result<MetaInfoFile, MetaInfoError>>,
+    #[serde(alias = "FieldSources", default)]
+    pub field_sources: HashMap<String, FieldSource>,
+}
+
+impl StreamRecord {
+    /// Schema version of records created by this version of the library
+    pub const SCHEMA_VERSION: u32 = 1;
+}
+
+impl From<StreamInfo> for StreamRecord {
+    fn from(info: StreamInfo) -> Self {
+        StreamRecord {
+            schema_version: StreamRecord::SCHEMA_VERSION,
+            server: info.Server,
+            public: info.Public,
+            ice_audio_info: info.IceAudioInfo,
+            icy_audio_info: info.AudioInfo,
+            name: info.Name,
+            description: info.Description,
+            content_type: info.Type,
+            homepage: info.Homepage,
+            genre: info.Genre,
+            bitrate: info.Bitrate,
+            sampling: info.Sampling,
+            channels: info.Channels,
+            quality: info.Quality,
+            codec_audio: info.CodecAudio,
+            codec_video: info.CodecVideo,
+            video_profile: info.VideoProfile,
+            container: info.Container,
+            hls: info.Hls,
+            logo_url: info.LogoUrl,
+            main_stream_url: info.MainStreamUrl,
+            icy_version: info.IcyVersion,
+            override_index_metadata: info.OverrideIndexMetaData,
+            country_code: info.CountryCode,
+            country_subdivision_code: info.CountrySubdivisonCode,
+            language_codes: info.LanguageCodes,
+            geo_lat_long: info.GeoLatLong,
+            decode_errors: info.DecodeErrors,
+            do_not_index: info.DoNotIndex,
+            ssl_error: info.SslError,
+            loudness: info.Loudness,
+            other_headers: info.OtherHeaders,
+            header_conflicts: info.HeaderConflicts,
+            dialect: info.Dialect,
+            server_status: info.ServerStatus,
+            meta_info_file: info.MetaInfoFile,
+            field_sources: info.FieldSources,
+        }
+    }
+}